@@ -1,7 +1,8 @@
 //! Handle `cargo add` arguments
 
 use cargo_edit::Dependency;
-use cargo_edit::{get_latest_dependency, CrateName};
+use cargo_edit::{emit, find_package, get_latest_dependency, print_member, CrateName, Event,
+                 MessageFormat};
 use semver;
 use std::path::PathBuf;
 
@@ -28,6 +29,12 @@ pub struct Args {
     pub flag_target: Option<String>,
     /// Optional dependency
     pub flag_optional: bool,
+    /// `--workspace-dep`
+    pub flag_workspace_dep: bool,
+    /// `--package`
+    pub flag_package: Option<String>,
+    /// `--workspace`
+    pub flag_workspace: bool,
     /// `Cargo.toml` path
     pub flag_manifest_path: Option<PathBuf>,
     /// `--version`
@@ -38,6 +45,16 @@ pub struct Args {
     pub flag_allow_prerelease: bool,
     /// '--quiet'
     pub flag_quiet: bool,
+    /// `--dry-run`
+    pub flag_dry_run: bool,
+    /// `--diff`
+    pub flag_diff: bool,
+    /// `--update-lock`
+    pub flag_update_lock: bool,
+    /// `--from`
+    pub flag_from: Option<PathBuf>,
+    /// `--message-format`
+    pub flag_message_format: String,
 }
 
 fn parse_version_req(s: &str) -> Result<&str> {
@@ -46,6 +63,27 @@ fn parse_version_req(s: &str) -> Result<&str> {
 }
 
 impl Args {
+    /// Get the requested output format
+    pub fn message_format(&self) -> Result<MessageFormat> {
+        Ok(self.flag_message_format.parse()?)
+    }
+
+    /// Print an event in the requested format, unless `--quiet` was given.
+    pub fn emit(&self, event: &Event) -> Result<()> {
+        if !self.flag_quiet {
+            emit(event, self.message_format()?)?;
+        }
+        Ok(())
+    }
+
+    /// Print the name of the package whose changes follow, unless `--quiet` was given.
+    pub fn print_member(&self, member: &str) -> Result<()> {
+        if !self.flag_quiet {
+            print_member(member, self.message_format()?);
+        }
+        Ok(())
+    }
+
     /// Get dependency section
     pub fn get_section(&self) -> Vec<String> {
         if self.flag_dev {
@@ -66,6 +104,25 @@ impl Args {
         }
     }
 
+    /// Get the sections to import dependencies from with `--from`: the section given with `--dev`,
+    /// `--build` or `--target`, if any.
+    pub fn get_import_sections(&self) -> Option<Vec<String>> {
+        if self.flag_dev || self.flag_build || self.flag_target.is_some() {
+            Some(self.get_section())
+        } else {
+            None
+        }
+    }
+
+    /// Get the manifest to edit: that of the package given with `--package`, if any. Otherwise
+    /// `--manifest-path` (or the `cwd`) is searched.
+    pub fn get_manifest_path(&self) -> Result<Option<PathBuf>> {
+        Ok(match self.flag_package {
+            Some(ref name) => Some(find_package(&self.flag_manifest_path, name)?),
+            None => self.flag_manifest_path.clone(),
+        })
+    }
+
     /// Build dependencies from arguments
     pub fn parse_dependencies(&self) -> Result<Vec<Dependency>> {
         if !self.arg_crates.is_empty() {
@@ -168,11 +225,19 @@ impl Default for Args {
             flag_path: None,
             flag_target: None,
             flag_optional: false,
+            flag_workspace_dep: false,
+            flag_package: None,
+            flag_workspace: false,
             flag_manifest_path: None,
             flag_version: false,
             flag_upgrade: None,
             flag_allow_prerelease: false,
             flag_quiet: false,
+            flag_dry_run: false,
+            flag_diff: false,
+            flag_update_lock: false,
+            flag_from: None,
+            flag_message_format: "human".to_owned(),
         }
     }
 }
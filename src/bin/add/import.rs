@@ -0,0 +1,179 @@
+//! `cargo add --from`: copy dependencies declared in another manifest.
+
+use std::path::{Path, PathBuf};
+
+use cargo_edit::{relative_path, Dependency, Manifest};
+use cargo_metadata;
+use toml_edit;
+
+use errors::*;
+
+/// A dependency declared in the manifest to import from.
+#[derive(Debug, Clone)]
+pub struct Import {
+    /// Path to the section containing the dependency, e.g. `["target", "cfg(unix)",
+    /// "dependencies"]`
+    pub section: Vec<String>,
+    /// The dependency, with its `path` (if any) as it is declared
+    pub dependency: Dependency,
+    /// The directory a relative `path` is relative to
+    base: PathBuf,
+}
+
+impl Import {
+    /// The dependency as it has to be declared in a manifest in `dir`, i.e. with a relative `path`
+    /// that points at the same crate from there.
+    pub fn relocate(&self, dir: &Path) -> Dependency {
+        match self.dependency.path() {
+            Some(path) if Path::new(path).is_relative() => {
+                let path = relative_path(dir, &self.base.join(path));
+                self.dependency.clone().set_path(&path.to_string_lossy())
+            }
+            _ => self.dependency.clone(),
+        }
+    }
+}
+
+/// Whether dependencies in `section` are selected by `filter`: a section kind such as
+/// `dev-dependencies` selects that kind of section for every target, a full path selects only
+/// that section.
+fn in_sections(filter: &[String], section: &[String]) -> bool {
+    if filter.len() == 1 {
+        section.last() == filter.last()
+    } else {
+        filter == section
+    }
+}
+
+/// Resolve a dependency inherited from the workspace of the manifest at `path`, i.e. one
+/// declared as `{ workspace = true }`. Features are added to those of the workspace's
+/// declaration, and whether it is optional is decided by the member.
+fn inherit(path: &Path, key: &str, item: &toml_edit::Item) -> Result<(Dependency, PathBuf)> {
+    let root = PathBuf::from(
+        cargo_metadata::metadata(Some(path))
+            .chain_err(|| "Failed to get workspace metadata")?
+            .workspace_root,
+    );
+    let manifest = Manifest::open(&Some(root.join("Cargo.toml")))?;
+    let definition = &manifest.data["workspace"]["dependencies"][key];
+    let dependency = Dependency::from_toml(key, definition).ok_or_else(|| {
+        Error::from(format!(
+            "The dependency `{}` is inherited, but not declared by the workspace of {}",
+            key,
+            path.display()
+        ))
+    })?;
+
+    let mut features = dependency
+        .features()
+        .map(|features| features.to_vec())
+        .unwrap_or_default();
+    for feature in item["features"].as_array().into_iter().flat_map(|a| a.iter()) {
+        if let Some(feature) = feature.as_str() {
+            if !features.iter().any(|known| known == feature) {
+                features.push(feature.to_string());
+            }
+        }
+    }
+    let dependency = if features.is_empty() {
+        dependency
+    } else {
+        dependency.set_features(features)
+    };
+    let optional = item["optional"].as_bool().unwrap_or(false);
+    Ok((dependency.set_optional(optional), root))
+}
+
+/// Read the dependencies declared in the manifest at `path`. Only the crates named in `crates`
+/// (by their key in the manifest or their package name) are read, unless it is empty. `sections`
+/// selects the sections to read from, see `in_sections`; all of them are read by default.
+pub fn read_imports(
+    path: &Path,
+    crates: &[String],
+    sections: Option<&[String]>,
+) -> Result<Vec<Import>> {
+    let manifest = Manifest::open(&Some(path.to_path_buf()))?;
+    let base = path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let mut imports = Vec::new();
+    for (section, table) in manifest.get_sections() {
+        let selected = match sections {
+            Some(filter) => in_sections(filter, &section),
+            None => true,
+        };
+        // Dependencies shared by a workspace are not dependencies of any package.
+        if section[0] == "workspace" || !selected {
+            continue;
+        }
+        let table = table.as_table_like().expect("Unexpected non-table");
+        for (key, item) in table.iter() {
+            let dependency = match Dependency::from_toml(key, item) {
+                Some(dependency) => dependency,
+                None => continue,
+            };
+            if !crates.is_empty()
+                && !crates
+                    .iter()
+                    .any(|name| name == key || name == &dependency.name)
+            {
+                continue;
+            }
+
+            let (dependency, base) = if dependency.is_workspace() {
+                inherit(path, key, item)?
+            } else {
+                (dependency, base.clone())
+            };
+            imports.push(Import {
+                section: section.clone(),
+                dependency,
+                base,
+            });
+        }
+    }
+
+    let mut missing = crates
+        .iter()
+        .filter(|name| {
+            !imports.iter().any(|import| {
+                import.dependency.toml_key() == name.as_str() || &import.dependency.name == *name
+            })
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    let table = path.display().to_string();
+    match missing.len() {
+        0 => {}
+        1 => Err(::cargo_edit::Error::from(
+            ::cargo_edit::ErrorKind::NonExistentDependency(missing.remove(0), table),
+        ))?,
+        _ => Err(::cargo_edit::Error::from(
+            ::cargo_edit::ErrorKind::NonExistentDependencies(missing, table),
+        ))?,
+    }
+    Ok(imports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn section_filter() {
+        let section = |path: &[&str]| path.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let target_dev = section(&["target", "cfg(unix)", "dev-dependencies"]);
+        assert!(in_sections(&section(&["dev-dependencies"]), &target_dev));
+        assert!(!in_sections(&section(&["dependencies"]), &target_dev));
+        assert!(in_sections(
+            &section(&["target", "cfg(unix)", "dev-dependencies"]),
+            &target_dev
+        ));
+        assert!(!in_sections(
+            &section(&["target", "cfg(windows)", "dev-dependencies"]),
+            &target_dev
+        ));
+    }
+}
@@ -5,25 +5,29 @@
     unused_qualifications
 )]
 
-extern crate atty;
+extern crate cargo_metadata;
 extern crate docopt;
 #[macro_use]
 extern crate error_chain;
 extern crate semver;
 #[macro_use]
 extern crate serde_derive;
-extern crate termcolor;
+extern crate toml_edit;
 
-use std::io::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
 extern crate cargo_edit;
-use cargo_edit::{Dependency, Manifest};
+use cargo_edit::{emit, print_dry_run, relative_path, workspace_members, ChangeSet, Dependency,
+                 Event, LocalManifest, MessageFormat};
 
 mod args;
 use args::Args;
 
+mod import;
+use import::{read_imports, Import};
+
 mod errors {
     error_chain!{
         errors {
@@ -40,6 +44,7 @@ mod errors {
         }
         links {
             CargoEditLib(::cargo_edit::Error, ::cargo_edit::ErrorKind);
+            CargoMetadata(::cargo_metadata::Error, ::cargo_metadata::ErrorKind);
         }
         foreign_links {
             Io(::std::io::Error);
@@ -50,6 +55,7 @@ use errors::*;
 
 static USAGE: &'static str = r#"
 Usage:
+    cargo add --from <manifest> [<crates>...] [--dev|--build] [options]
     cargo add <crate> [--dev|--build|--optional] [options]
     cargo add <crates>... [--dev|--build|--optional] [options]
     cargo add (-h|--help)
@@ -62,6 +68,9 @@ Specify what crate to add:
     --git <uri>             Specify a git repository to download the crate from. This does not work
                             if either a version or path (or both) is specified.
     --path <uri>            Specify the path the crate should be loaded from.
+    --from <manifest>       Copy the dependencies declared in another manifest, or only the given
+                            crates. With `--dev`, `--build` or `--target`, only the dependencies
+                            in that section are copied.
 
 Specify where to add the crate:
     -D --dev                Add crate as development dependency.
@@ -70,6 +79,10 @@ Specify where to add the crate:
                             for `dev-dependencies` or `build-dependencies`.
     --target <target>       Add as dependency to the given target platform. This does not work
                             for `dev-dependencies` or `build-dependencies`.
+    --workspace-dep         Add the crate to `[workspace.dependencies]` in the workspace root
+                            manifest, and inherit it here with `workspace = true`.
+    -p --package <name>     Add the crate to the workspace member with the given name.
+    --workspace             Add the crate to every member of the workspace.
 
 Options:
     --upgrade=<method>      Choose method of semantic version upgrade. Must be one of
@@ -78,6 +91,12 @@ Options:
     --manifest-path=<path>  Path to the manifest to add a dependency to.
     --allow-prerelease      Include prerelease versions when fetching from crates.io (e.g.
                             '0.6.0-alpha'). Defaults to false.
+    --dry-run               Print the changes to be made without making them.
+    --diff                  Print the changes to each manifest as a unified diff. Combine with
+                            `--dry-run` to only preview them.
+    --update-lock           Update Cargo.lock after writing the manifests, and undo the changes if
+                            the new dependencies can not be resolved.
+    --message-format=<fmt>  Output format, `human` or `json`. [default: human]
     -q --quiet              Do not print any output in case of success.
     -h --help               Show this help page.
     -V --version            Show version.
@@ -90,47 +109,110 @@ Please note that Cargo treats versions like "1.2.3" as "^1.2.3" (and that "^1.2.
 as ">=1.2.3 and <2.0.0"). By default, `cargo add` will use this format, as it is the one that the
 crates.io registry suggests. One goal of `cargo add` is to prevent you from using wildcard
 dependencies (version set to "*").
+
+With `--from`, each dependency is copied to the section it is declared in, with its features,
+`default-features` and `optional` settings, and a relative `path` is adjusted to point at the same
+crate from the manifest it is copied to. Dependencies inherited from the workspace of the other
+manifest are copied with the declaration of that workspace.
+
+With `--workspace-dep`, a relative `--path` is taken relative to the manifest the command is run
+against, and adjusted to point at the same crate from the workspace root manifest.
+
+With `--message-format json`, one JSON object is printed per event instead: `added` for each
+dependency added, `locked` for each change to Cargo.lock with `--update-lock`, and `error`.
 "#;
 
-fn print_msg(dep: &Dependency, section: &[String], optional: bool) -> Result<()> {
-    let colorchoice = if atty::is(atty::Stream::Stdout) {
-        ColorChoice::Auto
-    } else {
-        ColorChoice::Never
+/// Add the dependencies to `[workspace.dependencies]` in the workspace root manifest, and inherit
+/// them in the manifest the command was run against. A relative `--path` is relative to the
+/// latter, and is rebased onto the directory of the root manifest.
+fn handle_add_workspace_dep(
+    args: &Args,
+    manifest: LocalManifest,
+    deps: &[Dependency],
+    changes: &mut ChangeSet,
+) -> Result<()> {
+    let manifest_path = manifest.path().to_path_buf();
+    let root_path = Path::new(
+        &cargo_metadata::metadata(Some(&manifest_path))
+            .chain_err(|| "Failed to get workspace metadata")?
+            .workspace_root,
+    ).join("Cargo.toml");
+    let is_root = fs::canonicalize(&manifest_path)? == fs::canonicalize(&root_path)?;
+
+    let member_dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+    let root_dir = root_path.parent().unwrap_or_else(|| Path::new("."));
+
+    // With `--workspace`, the root manifest has already been edited for the previous members.
+    let (mut root, mut member) = match (changes.take(&root_path), is_root) {
+        (Some(root), true) => (root, None),
+        (Some(root), false) => (root, Some(manifest)),
+        (None, true) => (manifest, None),
+        (None, false) => (LocalManifest::try_new(&root_path)?, Some(manifest)),
     };
-    let mut output = StandardStream::stdout(colorchoice);
-    output.set_color(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(true))?;
-    write!(output, "{:>12}", "Adding")?;
-    output.reset()?;
-    write!(output, " {}", dep.name)?;
-    if let Some(version) = dep.version() {
-        write!(output, " v{}", version)?;
-    } else {
-        write!(output, " (unknown version)")?;
+
+    let workspace_section = ["workspace".to_owned(), "dependencies".to_owned()];
+    let section = args.get_section();
+    for dep in deps {
+        let event = Event::added(&section, dep, args.flag_optional, true);
+        args.emit(&event.in_manifest(member.as_ref().unwrap_or(&root).path()))?;
+        // Whether a dependency is optional can only be decided by the members.
+        let mut shared = dep.clone().set_optional(false);
+        if let Some(path) = dep.path().map(Path::new).filter(|path| path.is_relative()) {
+            let path = relative_path(root_dir, &member_dir.join(path));
+            shared = shared.set_path(&path.to_string_lossy());
+        }
+        root.insert_into_table(&workspace_section, &shared)?;
+
+        let inherited = Dependency::new(&dep.name)
+            .set_workspace()
+            .set_optional(args.flag_optional);
+        member
+            .as_mut()
+            .unwrap_or(&mut root)
+            .insert_into_table(&section, &inherited)?;
     }
-    write!(output, " to")?;
-    if optional {
-        write!(output, " optional")?;
+
+    changes.push(root);
+    if let Some(member) = member {
+        changes.push(member);
     }
-    let section = if section.len() == 1 {
-        section[0].clone()
-    } else {
-        format!("{} for target `{}`", &section[2], &section[1])
-    };
-    writeln!(output, " {}", section)?;
+
     Ok(())
 }
 
-fn handle_add(args: &Args) -> Result<()> {
-    let manifest_path = args.flag_manifest_path.as_ref().map(From::from);
-    let mut manifest = Manifest::open(&manifest_path)?;
-    let deps = &args.parse_dependencies()?;
+/// Open a manifest to add the dependencies to, and check that it is the manifest of a package. A
+/// virtual manifest is accepted with `--workspace-dep`, as it can hold the definitions.
+fn open_manifest(args: &Args, manifest_path: &Option<PathBuf>) -> Result<LocalManifest> {
+    let manifest = LocalManifest::find(manifest_path)?;
+    if args.flag_from.is_some() {
+        manifest.ensure_package(
+            "use `--package <name>` to add the crates to a member of the workspace, or \
+             `--workspace` to add them to every member",
+        )?;
+    } else if !args.flag_workspace_dep {
+        manifest.ensure_package(
+            "use `--package <name>` to add the crate to a member of the workspace, or \
+             `--workspace` to add it to every member",
+        )?;
+    }
+    Ok(manifest)
+}
+
+/// Add the dependencies to a single manifest.
+fn add_to_manifest(
+    args: &Args,
+    mut manifest: LocalManifest,
+    deps: &[Dependency],
+    changes: &mut ChangeSet,
+) -> Result<()> {
+    if args.flag_workspace_dep {
+        return handle_add_workspace_dep(args, manifest, deps, changes);
+    }
 
     deps.iter()
         .map(|dep| {
-            if !args.flag_quiet {
-                print_msg(dep, &args.get_section(), args.flag_optional)?;
-            }
+            let event = Event::added(&args.get_section(), dep, args.flag_optional, false);
+            args.emit(&event.in_manifest(manifest.path()))?;
             manifest
                 .insert_into_table(&args.get_section(), dep)
                 .map_err(Into::into)
@@ -141,9 +223,97 @@ fn handle_add(args: &Args) -> Result<()> {
             err
         })?;
 
-    let mut file = Manifest::find_file(&manifest_path)?;
-    manifest.write_to_file(&mut file)?;
+    changes.push(manifest);
+    Ok(())
+}
+
+/// Copy the dependencies imported with `--from` to a single manifest.
+fn import_to_manifest(
+    args: &Args,
+    mut manifest: LocalManifest,
+    imports: &[Import],
+    changes: &mut ChangeSet,
+) -> Result<()> {
+    let dir = manifest
+        .path()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    for import in imports {
+        let dep = import.relocate(&dir);
+        let event = Event::added(&import.section, &dep, dep.is_optional(), false);
+        args.emit(&event.in_manifest(manifest.path()))?;
+        manifest.insert_into_table(&import.section, &dep)?;
+    }
 
+    changes.push(manifest);
+    Ok(())
+}
+
+fn handle_add(args: &Args) -> Result<()> {
+    let human = !args.flag_quiet && args.message_format()? == MessageFormat::Human;
+    if args.flag_from.is_some() && args.flag_workspace_dep {
+        return Err("`--from` and `--workspace-dep` can not be combined".into());
+    }
+
+    // Open the manifests to edit first, so that an invalid one is reported before the
+    // dependencies are resolved, which may query crates.io.
+    let manifests = if args.flag_workspace {
+        workspace_members(&args.flag_manifest_path)?
+            .into_iter()
+            .map(|package| {
+                let manifest = open_manifest(args, &Some(PathBuf::from(package.manifest_path)))?;
+                Ok((Some(package.name), manifest))
+            })
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        vec![(None, open_manifest(args, &args.get_manifest_path()?)?)]
+    };
+
+    let (deps, imports) = match args.flag_from {
+        Some(ref from) => {
+            let sections = args.get_import_sections();
+            let imports = read_imports(from, &args.arg_crates, sections.as_deref())?;
+            (Vec::new(), imports)
+        }
+        None => (args.parse_dependencies()?, Vec::new()),
+    };
+
+    if args.flag_dry_run && human {
+        print_dry_run()?;
+    }
+
+    let mut changes = ChangeSet::new();
+    for (member, manifest) in manifests {
+        if let Some(ref member) = member {
+            args.print_member(member)?;
+        }
+        if args.flag_from.is_some() {
+            import_to_manifest(args, manifest, &imports, &mut changes)?;
+        } else {
+            add_to_manifest(args, manifest, &deps, &mut changes)?;
+        }
+    }
+
+    if args.flag_diff {
+        print!("{}", changes.diff());
+    }
+    if args.flag_dry_run {
+        return Ok(());
+    }
+    changes.apply()?;
+
+    if args.flag_update_lock {
+        let names = deps
+            .iter()
+            .chain(imports.iter().map(|import| &import.dependency))
+            .map(|dep| dep.name.clone())
+            .collect::<Vec<_>>();
+        for change in changes.update_lockfile(&names)? {
+            args.emit(&Event::locked(&change))?;
+        }
+    }
     Ok(())
 }
 
@@ -158,11 +328,10 @@ fn main() {
     }
 
     if let Err(err) = handle_add(&args) {
-        eprintln!("Command failed due to unhandled error: {}\n", err);
-
-        for e in err.iter().skip(1) {
-            eprintln!("Caused by: {}", e);
-        }
+        let causes = err.iter().skip(1).map(|e| e.to_string()).collect();
+        // Errors are reported even with `--quiet`, and an unknown format is reported for humans.
+        let format = args.message_format().unwrap_or(MessageFormat::Human);
+        let _ = emit(&Event::error(&err.to_string(), causes), format);
 
         if let Some(backtrace) = err.backtrace() {
             eprintln!("Backtrace: {:?}", backtrace);
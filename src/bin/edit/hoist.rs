@@ -0,0 +1,214 @@
+//! `cargo edit hoist`
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use cargo_edit::{emit, print_dry_run, registry_requirement, requirement_at_version,
+                 requirement_change, unify_requirements, ChangeSet, Dependency, Event,
+                 LocalManifest, MessageFormat, RequirementChange, Unification};
+use cargo_metadata;
+use semver;
+
+use errors::*;
+
+/// A dependency on a registry crate, declared in one of the members.
+struct Declaration {
+    /// Index of the manifest in `Workspace::manifests`
+    manifest: usize,
+    /// Name of the member
+    member: String,
+    /// Path to the section containing the dependency
+    section: Vec<String>,
+    /// The version requirement
+    requirement: String,
+    /// Whether the member declares the dependency as optional
+    optional: bool,
+}
+
+/// All manifests of a workspace. The root manifest always comes first; members whose manifest is
+/// the root manifest share it.
+struct Workspace {
+    manifests: Vec<LocalManifest>,
+    /// Names of the members, and the index of their manifest
+    members: Vec<(String, usize)>,
+}
+
+impl Workspace {
+    fn open(manifest_path: Option<&Path>) -> Result<Self> {
+        let metadata = cargo_metadata::metadata(manifest_path)
+            .chain_err(|| "Failed to get workspace metadata")?;
+        let root_path = Path::new(&metadata.workspace_root).join("Cargo.toml");
+
+        let mut manifests = vec![LocalManifest::try_new(&root_path)?];
+        let mut members = Vec::new();
+        for package in metadata.packages {
+            let path = Path::new(&package.manifest_path);
+            if path == root_path {
+                members.push((package.name, 0));
+            } else {
+                manifests.push(LocalManifest::try_new(path)?);
+                members.push((package.name, manifests.len() - 1));
+            }
+        }
+
+        Ok(Workspace { manifests, members })
+    }
+
+    /// Find the registry dependencies declared by two or more members, keyed by crate name.
+    fn shared_dependencies(&self) -> BTreeMap<String, Vec<Declaration>> {
+        let mut declarations: BTreeMap<String, Vec<Declaration>> = BTreeMap::new();
+
+        for &(ref member, index) in &self.members {
+            for (section, table) in self.manifests[index].get_sections() {
+                if section[0] == "workspace" {
+                    continue;
+                }
+                let table = table.as_table_like().expect("Unexpected non-table");
+                for (name, item) in table.iter() {
+                    if let Some(requirement) = registry_requirement(item) {
+                        declarations
+                            .entry(name.to_string())
+                            .or_default()
+                            .push(Declaration {
+                                manifest: index,
+                                member: member.clone(),
+                                section: section.clone(),
+                                requirement: requirement.to_string(),
+                                optional: item["optional"].as_bool().unwrap_or(false),
+                            });
+                    }
+                }
+            }
+        }
+
+        declarations
+            .into_iter()
+            .filter(|(_, found)| {
+                found
+                    .iter()
+                    .any(|declaration| declaration.member != found[0].member)
+            })
+            .collect()
+    }
+}
+
+/// The members declaring a dependency, with their requirements.
+fn describe_members(found: &[Declaration]) -> String {
+    found
+        .iter()
+        .map(|d| format!("{} ({})", d.member, d.requirement))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether two requirements are written alike, e.g. `1.0` and `^1.0`.
+fn same_requirement(a: &str, b: &str) -> bool {
+    match (semver::VersionReq::parse(a), semver::VersionReq::parse(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.trim() == b.trim(),
+    }
+}
+
+/// Move dependencies declared by two or more workspace members into `[workspace.dependencies]`,
+/// and make the members inherit them.
+pub fn handle_hoist(
+    manifest_path: Option<&Path>,
+    dry_run: bool,
+    diff: bool,
+    format: MessageFormat,
+) -> Result<()> {
+    let mut workspace = Workspace::open(manifest_path)?;
+    let shared = workspace.shared_dependencies();
+    let workspace_section = ["workspace".to_string(), "dependencies".to_string()];
+    let root_manifest_path = workspace.manifests[0].path().to_path_buf();
+
+    if dry_run && format == MessageFormat::Human {
+        print_dry_run()?;
+    }
+
+    for (name, found) in shared {
+        // An existing definition in the root manifest is unified with the members' requirements,
+        // unless it is not a plain registry dependency.
+        let existing = &workspace.manifests[0].data["workspace"]["dependencies"][&name];
+        let existing = if existing.is_none() {
+            None
+        } else if let Some(requirement) = registry_requirement(existing) {
+            Some(requirement.to_string())
+        } else {
+            continue;
+        };
+
+        let requirements = found
+            .iter()
+            .map(|d| d.requirement.as_str())
+            .chain(existing.as_deref())
+            .collect::<Vec<_>>();
+        let skip = |reason: &str| {
+            let mut reason = format!("{} in {}", reason, describe_members(&found));
+            if let Some(ref existing) = existing {
+                reason.push_str(&format!(" and the workspace ({})", existing));
+            }
+            let event = Event::skipped(&[], &name, &reason);
+            emit(&event.in_manifest(&root_manifest_path), format)
+        };
+
+        // Members requiring semver incompatible versions can not share a definition.
+        let version = match unify_requirements(requirements.iter().cloned()) {
+            Some(Unification::Unified(version)) => version,
+            Some(Unification::Conflict(_)) => {
+                skip("incompatible requirements")?;
+                continue;
+            }
+            None => continue,
+        };
+        // Nor can members whose requirements still differ once raised to that version, e.g. `1.0`
+        // and `~1.0`, as one of them would be narrowed or widened. Requirements that already
+        // require it are kept as they are written.
+        let raised = requirements
+            .iter()
+            .map(|&requirement| match requirement_at_version(requirement, &version) {
+                Some(ref raised)
+                    if requirement_change(requirement, raised) != RequirementChange::Unchanged =>
+                {
+                    raised.clone()
+                }
+                _ => requirement.to_string(),
+            })
+            .collect::<Vec<_>>();
+        if !raised.iter().all(|r| same_requirement(r, &raised[0])) {
+            skip("differing requirements")?;
+            continue;
+        }
+        let requirement = raised[0].clone();
+
+        let members = found
+            .iter()
+            .map(|d| (d.member.clone(), d.requirement.clone()))
+            .collect::<Vec<_>>();
+        let event = Event::hoisted(&name, &requirement, &members);
+        emit(&event.in_manifest(&root_manifest_path), format)?;
+
+        let definition = Dependency::new(&name).set_version(&requirement);
+        workspace.manifests[0].insert_into_table(&workspace_section, &definition)?;
+        for declaration in &found {
+            let inherited = Dependency::new(&name)
+                .set_workspace()
+                .set_optional(declaration.optional);
+            workspace.manifests[declaration.manifest]
+                .insert_into_table(&declaration.section, &inherited)?;
+        }
+    }
+
+    let mut changes = ChangeSet::new();
+    for manifest in workspace.manifests {
+        changes.push(manifest);
+    }
+    if diff {
+        print!("{}", changes.diff());
+    }
+    if !dry_run {
+        changes.apply()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+//! `cargo edit lint`
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use atty;
+use cargo_edit::{lint, Lint, LocalManifest, MessageFormat};
+use serde_json;
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+use errors::*;
+
+/// A lint, together with the manifest it was found in. This is what gets printed for
+/// `--message-format json`.
+#[derive(Debug, Serialize)]
+struct LintMessage<'a> {
+    manifest_path: &'a Path,
+    #[serde(flatten)]
+    lint: &'a Lint,
+}
+
+fn print_human(manifest_path: &Path, lint: &Lint) -> Result<()> {
+    let colorchoice = if atty::is(atty::Stream::Stdout) {
+        ColorChoice::Auto
+    } else {
+        ColorChoice::Never
+    };
+    let mut output = StandardStream::stdout(colorchoice);
+    output.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)).set_bold(true))?;
+    write!(output, "warning")?;
+    output.reset()?;
+    writeln!(output, ": {}", lint.message)?;
+    writeln!(output, "  --> {}", manifest_path.display())?;
+    Ok(())
+}
+
+/// Lint all given manifests and print the problems found. Returns the exit code: 2 if there were
+/// any problems, 0 otherwise.
+pub fn handle_lint(manifest_paths: &[PathBuf], format: MessageFormat) -> Result<i32> {
+    let mut found_problems = false;
+
+    for manifest_path in manifest_paths {
+        let manifest = LocalManifest::try_new(manifest_path)?;
+
+        for lint in lint(&manifest) {
+            found_problems = true;
+            match format {
+                MessageFormat::Human => print_human(manifest_path, &lint)?,
+                MessageFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string(&LintMessage {
+                        manifest_path,
+                        lint: &lint,
+                    })?
+                ),
+            }
+        }
+    }
+
+    Ok(if found_problems { 2 } else { 0 })
+}
@@ -0,0 +1,179 @@
+//! `cargo edit`
+#![warn(
+    missing_docs, missing_debug_implementations, missing_copy_implementations, trivial_casts,
+    trivial_numeric_casts, unsafe_code, unstable_features, unused_import_braces,
+    unused_qualifications
+)]
+
+extern crate atty;
+extern crate cargo_metadata;
+extern crate docopt;
+#[macro_use]
+extern crate error_chain;
+extern crate semver;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate termcolor;
+
+use std::path::{Path, PathBuf};
+use std::process;
+
+extern crate cargo_edit;
+use cargo_edit::{emit, find, Event, MessageFormat};
+
+mod hoist;
+mod lint;
+mod undo;
+
+mod errors {
+    error_chain!{
+        links {
+            CargoEditLib(::cargo_edit::Error, ::cargo_edit::ErrorKind);
+            CargoMetadata(::cargo_metadata::Error, ::cargo_metadata::ErrorKind);
+        }
+        foreign_links {
+            Io(::std::io::Error);
+            Json(::serde_json::Error);
+        }
+    }
+}
+use errors::*;
+
+static USAGE: &'static str = r"
+Usage:
+    cargo edit lint [options]
+    cargo edit hoist [--dry-run] [--diff] [options]
+    cargo edit undo [options]
+    cargo edit (-h|--help)
+    cargo edit --version
+
+Options:
+    --all                   Run against all packages in the workspace.
+    --dry-run               Print the changes to be made without making them.
+    --diff                  Print the changes to each manifest as a unified diff. Combine with
+                            `--dry-run` to only preview them.
+    --manifest-path=<path>  Path to the manifest to run against.
+    --message-format=<fmt>  Output format, `human` or `json`. [default: human]
+    -h --help               Show this help page.
+    -V --version            Show version.
+
+Subcommands:
+    lint                    Check the dependencies in the manifest for common mistakes: wildcard
+                            (`*`) requirements, conflicting requirements for the same crate,
+                            path dependencies without a version in publishable crates, git
+                            dependencies without a pinned `rev` and optional dev-dependencies.
+                            Exits with status 2 if any problems are found.
+    hoist                   Move dependencies declared by two or more members of the workspace to
+                            `[workspace.dependencies]` in the root manifest, raised to the highest
+                            of their version requirements, and make the members inherit them with
+                            `workspace = true`. Members keep their own `features` and `optional`.
+                            Dependencies whose requirements are not semver compatible, e.g. `0.2`
+                            and `1.0`, use different operators, e.g. `1.0` and `~1.0`, or pin
+                            different exact versions, are left alone.
+    undo                    Restore the manifests written by the last command run in the
+                            workspace, e.g. `cargo upgrade --all`. Refuses if any of them has been
+                            changed since. The last 20 commands are kept in a journal in
+                            `target/cargo-edit/journal`, and can be undone one after the other.
+
+With `--message-format json`, one JSON object is printed per lint, or per event: `hoisted` and
+`skipped` for `hoist`, `undone` and `restored` for `undo`, and `error`.
+";
+
+/// Docopts input args.
+#[derive(Debug, Deserialize)]
+struct Args {
+    /// `lint`
+    cmd_lint: bool,
+    /// `hoist`
+    cmd_hoist: bool,
+    /// `undo`
+    cmd_undo: bool,
+    /// `--all`
+    flag_all: bool,
+    /// `--dry-run`
+    flag_dry_run: bool,
+    /// `--diff`
+    flag_diff: bool,
+    /// `--manifest-path`
+    flag_manifest_path: Option<String>,
+    /// `--message-format`
+    flag_message_format: String,
+    /// `--version`
+    flag_version: bool,
+}
+
+impl Args {
+    /// Get the requested output format
+    fn message_format(&self) -> Result<MessageFormat> {
+        Ok(self.flag_message_format.parse()?)
+    }
+
+    /// Get the paths of the manifests to run against: either the given (or found) one, or all
+    /// manifests in the workspace if `--all` was passed.
+    fn manifest_paths(&self) -> Result<Vec<PathBuf>> {
+        if self.flag_all {
+            Ok(
+                cargo_metadata::metadata(self.flag_manifest_path.as_ref().map(Path::new))
+                    .chain_err(|| "Failed to get workspace metadata")?
+                    .packages
+                    .into_iter()
+                    .map(|package| PathBuf::from(package.manifest_path))
+                    .collect(),
+            )
+        } else {
+            Ok(vec![find(&self.flag_manifest_path.as_ref().map(PathBuf::from))?])
+        }
+    }
+}
+
+/// Main processing function. Returns the exit code on success, so that subcommands can signal
+/// their results to CI.
+fn process(args: &Args) -> Result<i32> {
+    if args.cmd_lint {
+        lint::handle_lint(&args.manifest_paths()?, args.message_format()?)
+    } else if args.cmd_hoist {
+        hoist::handle_hoist(
+            args.flag_manifest_path.as_ref().map(Path::new),
+            args.flag_dry_run,
+            args.flag_diff,
+            args.message_format()?,
+        )?;
+        Ok(0)
+    } else if args.cmd_undo {
+        undo::handle_undo(
+            args.flag_manifest_path.as_ref().map(Path::new),
+            args.message_format()?,
+        )?;
+        Ok(0)
+    } else {
+        Ok(0)
+    }
+}
+
+fn main() {
+    let args = docopt::Docopt::new(USAGE)
+        .and_then(|d| d.deserialize::<Args>())
+        .unwrap_or_else(|err| err.exit());
+
+    if args.flag_version {
+        println!("cargo-edit version {}", env!("CARGO_PKG_VERSION"));
+        process::exit(0);
+    }
+
+    match process(&args) {
+        Ok(code) => process::exit(code),
+        Err(err) => {
+            // An unknown format is itself reported for humans.
+            let format = args.message_format().unwrap_or(MessageFormat::Human);
+            let causes = err.iter().skip(1).map(|e| e.to_string()).collect();
+            let _ = emit(&Event::error(&err.to_string(), causes), format);
+
+            if let Some(backtrace) = err.backtrace() {
+                eprintln!("Backtrace: {:?}", backtrace);
+            }
+
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! `cargo edit undo`
+
+use std::path::Path;
+
+use cargo_edit::{emit, find, Event, Journal, MessageFormat};
+
+use errors::*;
+
+/// Restore the manifests written by the last command run in the workspace.
+pub fn handle_undo(manifest_path: Option<&Path>, format: MessageFormat) -> Result<()> {
+    let manifest_path = find(&manifest_path.map(Path::to_path_buf))?;
+    let entry = Journal::for_manifest(&manifest_path)?.undo()?;
+
+    emit(&Event::undone(&entry.operation), format)?;
+    for file in &entry.files {
+        emit(&Event::restored(&file.path), format)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,419 @@
+//! `cargo list`
+#![warn(
+    missing_docs, missing_debug_implementations, missing_copy_implementations, trivial_casts,
+    trivial_numeric_casts, unsafe_code, unstable_features, unused_import_braces,
+    unused_qualifications
+)]
+
+extern crate atty;
+extern crate cargo_metadata;
+extern crate docopt;
+#[macro_use]
+extern crate error_chain;
+extern crate semver;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate termcolor;
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+extern crate cargo_edit;
+use cargo_edit::{emit, find, find_package, get_latest_dependency, list_dependencies,
+                 workspace_members, Event, ListedDependency, LocalManifest, MessageFormat,
+                 SourceKind};
+
+mod errors {
+    error_chain!{
+        links {
+            CargoEditLib(::cargo_edit::Error, ::cargo_edit::ErrorKind);
+            CargoMetadata(::cargo_metadata::Error, ::cargo_metadata::ErrorKind);
+        }
+        foreign_links {
+            Io(::std::io::Error);
+            Json(::serde_json::Error);
+        }
+    }
+}
+use errors::*;
+
+static USAGE: &'static str = r"
+Usage:
+    cargo list [options]
+    cargo list (-h|--help)
+    cargo list --version
+
+Options:
+    -p --package <name>     List the dependencies of the workspace member with the given name.
+    --workspace             List the dependencies of every member of the workspace.
+    --manifest-path=<path>  Path to the manifest to list the dependencies of.
+    --table                 Print a table with one row per dependency, instead of a tree.
+    --outdated              Look up the latest version of each registry dependency on crates.io,
+                            and mark the dependencies whose requirement does not allow it.
+    --allow-prerelease      Include prerelease versions when looking up the latest versions.
+    --message-format=<fmt>  Output format, `human` or `json`. [default: human]
+    -h --help               Show this help page.
+    -V --version            Show version.
+
+List the dependencies in every section of a Cargo.toml manifest: normal, development, build and
+target-specific dependencies, with their version requirement, source (registry, git, path or
+workspace), features, and whether they are optional. With `--message-format json`, one JSON object
+is printed per dependency.
+";
+
+/// Docopts input args.
+#[derive(Debug, Deserialize)]
+struct Args {
+    /// `--package`
+    flag_package: Option<String>,
+    /// `--workspace`
+    flag_workspace: bool,
+    /// `--manifest-path`
+    flag_manifest_path: Option<String>,
+    /// `--table`
+    flag_table: bool,
+    /// `--outdated`
+    flag_outdated: bool,
+    /// `--allow-prerelease`
+    flag_allow_prerelease: bool,
+    /// `--message-format`
+    flag_message_format: String,
+    /// `--version`
+    flag_version: bool,
+}
+
+impl Args {
+    /// Get the requested output format
+    fn message_format(&self) -> Result<MessageFormat> {
+        Ok(self.flag_message_format.parse()?)
+    }
+
+    /// Get the manifests to list: those of every member with `--workspace`, that of the package
+    /// given with `--package`, or the given (or found) one.
+    fn manifest_paths(&self) -> Result<Vec<PathBuf>> {
+        let manifest_path = self.flag_manifest_path.as_ref().map(PathBuf::from);
+        if self.flag_workspace {
+            Ok(workspace_members(&manifest_path)?
+                .into_iter()
+                .map(|package| PathBuf::from(package.manifest_path))
+                .collect())
+        } else if let Some(ref name) = self.flag_package {
+            Ok(vec![find_package(&manifest_path, name)?])
+        } else {
+            Ok(vec![find(&manifest_path)?])
+        }
+    }
+}
+
+/// A dependency, together with the manifest it is declared in and its latest version. This is
+/// what gets printed for `--message-format json`.
+#[derive(Debug, Serialize)]
+struct ListMessage<'a> {
+    manifest_path: &'a Path,
+    #[serde(flatten)]
+    dependency: &'a ListedDependency,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latest: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outdated: Option<bool>,
+}
+
+/// The latest version of a dependency, and whether its requirement does not allow it.
+#[derive(Debug, Clone)]
+struct Latest {
+    version: String,
+    outdated: bool,
+}
+
+impl Latest {
+    fn new(requirement: &str, version: String) -> Self {
+        let outdated = match (
+            semver::VersionReq::parse(requirement),
+            semver::Version::parse(&version),
+        ) {
+            (Ok(requirement), Ok(latest)) => !requirement.matches(&latest),
+            _ => requirement != version,
+        };
+        Latest { version, outdated }
+    }
+}
+
+/// Look up the latest versions of the registry dependencies on crates.io. Each crate is only
+/// looked up once; crates that can not be found are reported and skipped.
+fn lookup_latest(
+    dependencies: &[ListedDependency],
+    allow_prerelease: bool,
+    cache: &mut HashMap<String, Option<String>>,
+) -> Vec<Option<Latest>> {
+    dependencies
+        .iter()
+        .map(|dependency| {
+            let requirement = match dependency.requirement {
+                Some(ref requirement) if dependency.is_registry() => requirement,
+                _ => return None,
+            };
+            let version = cache
+                .entry(dependency.package.clone())
+                .or_insert_with(|| {
+                    match get_latest_dependency(&dependency.package, allow_prerelease) {
+                        Ok(latest) => latest.version().map(|version| version.to_string()),
+                        Err(err) => {
+                            eprintln!(
+                                "WARN: Could not look up the latest version of `{}`: {}",
+                                dependency.package, err
+                            );
+                            None
+                        }
+                    }
+                })
+                .clone()?;
+            Some(Latest::new(requirement, version))
+        })
+        .collect()
+}
+
+/// Describe a dependency in a line of the tree, e.g. `json 1.0 (package: serde_json, optional)`.
+fn describe(dependency: &ListedDependency) -> String {
+    let mut description = dependency.name.clone();
+    if let Some(ref requirement) = dependency.requirement {
+        description.push(' ');
+        description.push_str(requirement);
+    }
+
+    let mut details = Vec::new();
+    if dependency.package != dependency.name {
+        details.push(format!("package: {}", dependency.package));
+    }
+    details.extend(source(dependency));
+    if dependency.optional {
+        details.push("optional".to_string());
+    }
+    if !dependency.features.is_empty() {
+        details.push(format!("features: {}", dependency.features.join(", ")));
+    }
+    if !details.is_empty() {
+        description.push_str(&format!(" ({})", details.join(", ")));
+    }
+    description
+}
+
+/// Describe where a dependency comes from, unless it is a registry.
+fn source(dependency: &ListedDependency) -> Option<String> {
+    let location = dependency.location.as_deref().unwrap_or("");
+    match dependency.source {
+        SourceKind::Registry => None,
+        SourceKind::Git => Some(format!("git: {}", location)),
+        SourceKind::Path => Some(format!("path: {}", location)),
+        SourceKind::Workspace => Some("workspace".to_string()),
+    }
+}
+
+/// What a manifest is called in the output: the name and version of its package, or its path if
+/// it has none.
+fn manifest_label(manifest: &LocalManifest) -> String {
+    match (manifest.package_name(), manifest.package_version()) {
+        (Some(name), Some(version)) => format!("{} v{}", name, version),
+        (Some(name), None) => name.to_string(),
+        _ => manifest.path().display().to_string(),
+    }
+}
+
+fn stdout() -> StandardStream {
+    let colorchoice = if atty::is(atty::Stream::Stdout) {
+        ColorChoice::Auto
+    } else {
+        ColorChoice::Never
+    };
+    StandardStream::stdout(colorchoice)
+}
+
+/// Print a mark for an outdated dependency.
+fn print_outdated(output: &mut StandardStream, latest: &Latest) -> Result<()> {
+    output.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)).set_bold(true))?;
+    write!(output, " (outdated, latest: {})", latest.version)?;
+    output.reset()?;
+    Ok(())
+}
+
+/// Print the dependencies as a tree, grouped by section.
+fn print_tree(
+    label: &str,
+    dependencies: &[ListedDependency],
+    latest: &[Option<Latest>],
+) -> Result<()> {
+    let mut output = stdout();
+    writeln!(output, "{}", label)?;
+
+    let mut sections: Vec<(String, Vec<usize>)> = Vec::new();
+    for (index, dependency) in dependencies.iter().enumerate() {
+        let section = dependency.section.join(".");
+        match sections.iter_mut().find(|(name, _)| *name == section) {
+            Some((_, indices)) => indices.push(index),
+            None => sections.push((section, vec![index])),
+        }
+    }
+
+    for (section_index, (section, indices)) in sections.iter().enumerate() {
+        let last_section = section_index + 1 == sections.len();
+        let (branch, indent) = if last_section {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        writeln!(output, "{}{}", branch, section)?;
+
+        for (position, &index) in indices.iter().enumerate() {
+            let branch = if position + 1 == indices.len() {
+                "└── "
+            } else {
+                "├── "
+            };
+            write!(output, "{}{}{}", indent, branch, describe(&dependencies[index]))?;
+            if let Some(ref latest) = latest[index] {
+                if latest.outdated {
+                    print_outdated(&mut output, latest)?;
+                }
+            }
+            writeln!(output)?;
+        }
+    }
+    Ok(())
+}
+
+/// Print the dependencies as a table, with one row per dependency.
+fn print_table(dependencies: &[ListedDependency], latest: &[Option<Latest>]) -> Result<()> {
+    let mut header = vec![
+        "Section",
+        "Crate",
+        "Requirement",
+        "Source",
+        "Features",
+        "Optional",
+    ].into_iter()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let with_latest = latest.iter().any(Option::is_some);
+    if with_latest {
+        header.push("Latest".to_string());
+    }
+
+    let rows = dependencies
+        .iter()
+        .zip(latest)
+        .map(|(dependency, latest)| {
+            let name = if dependency.package == dependency.name {
+                dependency.name.clone()
+            } else {
+                format!("{} ({})", dependency.name, dependency.package)
+            };
+            let mut row = vec![
+                dependency.section.join("."),
+                name,
+                dependency.requirement.clone().unwrap_or_default(),
+                source(dependency).unwrap_or_else(|| "registry".to_string()),
+                dependency.features.join(", "),
+                if dependency.optional { "yes" } else { "" }.to_string(),
+            ];
+            if with_latest {
+                row.push(match *latest {
+                    Some(ref latest) if latest.outdated => format!("{} (outdated)", latest.version),
+                    Some(ref latest) => latest.version.clone(),
+                    None => String::new(),
+                });
+            }
+            row
+        })
+        .collect::<Vec<_>>();
+
+    let widths = (0..header.len())
+        .map(|column| {
+            rows.iter()
+                .chain(Some(&header))
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+
+    let mut output = stdout();
+    for row in Some(&header).into_iter().chain(&rows) {
+        let line = row.iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        writeln!(output, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+fn handle_list(args: &Args) -> Result<()> {
+    let format = args.message_format()?;
+    let manifest_paths = args.manifest_paths()?;
+    let mut cache = HashMap::new();
+
+    for (index, manifest_path) in manifest_paths.iter().enumerate() {
+        let manifest = LocalManifest::try_new(manifest_path)?;
+        let dependencies = list_dependencies(&manifest);
+        let latest = if args.flag_outdated {
+            lookup_latest(&dependencies, args.flag_allow_prerelease, &mut cache)
+        } else {
+            vec![None; dependencies.len()]
+        };
+
+        match format {
+            MessageFormat::Json => for (dependency, latest) in dependencies.iter().zip(&latest) {
+                println!(
+                    "{}",
+                    serde_json::to_string(&ListMessage {
+                        manifest_path,
+                        dependency,
+                        latest: latest.as_ref().map(|latest| latest.version.as_str()),
+                        outdated: latest.as_ref().map(|latest| latest.outdated),
+                    })?
+                );
+            },
+            MessageFormat::Human => {
+                if index > 0 {
+                    println!();
+                }
+                if args.flag_table {
+                    println!("{}:", manifest_label(&manifest));
+                    print_table(&dependencies, &latest)?;
+                } else {
+                    print_tree(&manifest_label(&manifest), &dependencies, &latest)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args = docopt::Docopt::new(USAGE)
+        .and_then(|d| d.deserialize::<Args>())
+        .unwrap_or_else(|err| err.exit());
+
+    if args.flag_version {
+        println!("cargo-list version {}", env!("CARGO_PKG_VERSION"));
+        process::exit(0);
+    }
+
+    if let Err(err) = handle_list(&args) {
+        // An unknown format is itself reported for humans.
+        let format = args.message_format().unwrap_or(MessageFormat::Human);
+        let causes = err.iter().skip(1).map(|e| e.to_string()).collect();
+        let _ = emit(&Event::error(&err.to_string(), causes), format);
+
+        if let Some(backtrace) = err.backtrace() {
+            eprintln!("Backtrace: {:?}", backtrace);
+        }
+
+        process::exit(1);
+    }
+}
@@ -0,0 +1,186 @@
+//! Handle `cargo patch` arguments
+
+use cargo_edit::{emit, CrateName, Dependency, Event, GitReference, MessageFormat};
+
+use errors::*;
+
+#[derive(Debug, Deserialize)]
+/// Docopts input args.
+pub struct Args {
+    /// `add` subcommand
+    pub cmd_add: bool,
+    /// `rm` subcommand
+    pub cmd_rm: bool,
+    /// `list` subcommand
+    pub cmd_list: bool,
+    /// Crate name
+    pub arg_crate: String,
+    /// Crate directory path
+    pub flag_path: Option<String>,
+    /// Git repo Path
+    pub flag_git: Option<String>,
+    /// Git branch
+    pub flag_branch: Option<String>,
+    /// Git tag
+    pub flag_tag: Option<String>,
+    /// Git revision
+    pub flag_rev: Option<String>,
+    /// Source to patch
+    pub flag_source: Option<String>,
+    /// Write a `[replace]` entry
+    pub flag_replace: bool,
+    /// `Cargo.toml` path
+    pub flag_manifest_path: Option<String>,
+    /// `--version`
+    pub flag_version: bool,
+    /// '--quiet'
+    pub flag_quiet: bool,
+    /// `--dry-run`
+    pub flag_dry_run: bool,
+    /// `--diff`
+    pub flag_diff: bool,
+    /// `--message-format`
+    pub flag_message_format: String,
+}
+
+impl Args {
+    /// Get the requested output format
+    pub fn message_format(&self) -> Result<MessageFormat> {
+        Ok(self.flag_message_format.parse()?)
+    }
+
+    /// Print an event in the requested format, unless `--quiet` was given.
+    pub fn emit(&self, event: &Event) -> Result<()> {
+        if !self.flag_quiet {
+            emit(event, self.message_format()?)?;
+        }
+        Ok(())
+    }
+
+    /// Get the name of the crate to override, without any version
+    pub fn get_crate_name(&self) -> Result<String> {
+        Ok(match CrateName::new(&self.arg_crate).parse_as_version()? {
+            Some(dependency) => dependency.name,
+            None => self.arg_crate.clone(),
+        })
+    }
+
+    /// Get the version of the crate to replace, e.g. `1.0.80` for `serde@1.0.80`
+    pub fn get_replaced_version(&self) -> Result<String> {
+        CrateName::new(&self.arg_crate)
+            .parse_as_version()?
+            .and_then(|dependency| dependency.version().map(String::from))
+            .ok_or_else(|| ErrorKind::ReplaceWithoutVersion(self.arg_crate.clone()).into())
+    }
+
+    /// Build the overriding dependency from arguments. Replacements carry the version of the
+    /// crate they replace.
+    pub fn parse_dependency(&self) -> Result<Dependency> {
+        let dependency = Dependency::new(&self.get_crate_name()?);
+        let dependency = if self.flag_replace {
+            dependency.set_version(&self.get_replaced_version()?)
+        } else {
+            dependency
+        };
+
+        Ok(match (self.flag_path.as_ref(), self.flag_git.as_ref()) {
+            (Some(path), _) => dependency.set_path(path),
+            (None, Some(repo)) => {
+                let dependency = dependency.set_git(repo);
+                match (
+                    self.flag_branch.clone(),
+                    self.flag_tag.clone(),
+                    self.flag_rev.clone(),
+                ) {
+                    (Some(branch), _, _) => {
+                        dependency.set_git_reference(GitReference::Branch(branch))
+                    }
+                    (_, Some(tag), _) => dependency.set_git_reference(GitReference::Tag(tag)),
+                    (_, _, Some(rev)) => dependency.set_git_reference(GitReference::Rev(rev)),
+                    _ => dependency,
+                }
+            }
+            (None, None) => unreachable!("docopt requires either `--path` or `--git`"),
+        })
+    }
+}
+
+impl Default for Args {
+    fn default() -> Args {
+        Args {
+            cmd_add: false,
+            cmd_rm: false,
+            cmd_list: false,
+            arg_crate: "demo".to_owned(),
+            flag_path: None,
+            flag_git: None,
+            flag_branch: None,
+            flag_tag: None,
+            flag_rev: None,
+            flag_source: None,
+            flag_replace: false,
+            flag_manifest_path: None,
+            flag_version: false,
+            flag_quiet: false,
+            flag_dry_run: false,
+            flag_diff: false,
+            flag_message_format: "human".to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_edit::{Dependency, GitReference};
+
+    #[test]
+    fn test_git_dependency_parsing() {
+        let args = Args {
+            cmd_add: true,
+            arg_crate: "serde".to_owned(),
+            flag_git: Some("https://github.com/serde-rs/serde".to_owned()),
+            flag_branch: Some("fix".to_owned()),
+            ..Args::default()
+        };
+
+        assert_eq!(
+            args.parse_dependency().unwrap(),
+            Dependency::new("serde")
+                .set_git("https://github.com/serde-rs/serde")
+                .set_git_reference(GitReference::Branch("fix".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_replace_dependency_parsing() {
+        let args = Args {
+            cmd_add: true,
+            arg_crate: "serde@1.0.80".to_owned(),
+            flag_path: Some("../serde".to_owned()),
+            flag_replace: true,
+            ..Args::default()
+        };
+
+        assert_eq!(args.get_crate_name().unwrap(), "serde");
+        assert_eq!(
+            args.parse_dependency().unwrap(),
+            Dependency::new("serde")
+                .set_version("1.0.80")
+                .set_path("../serde")
+        );
+    }
+
+    #[test]
+    fn test_replace_requires_version() {
+        let args = Args {
+            cmd_add: true,
+            arg_crate: "serde".to_owned(),
+            flag_path: Some("../serde".to_owned()),
+            flag_replace: true,
+            ..Args::default()
+        };
+
+        assert!(args.parse_dependency().is_err());
+    }
+}
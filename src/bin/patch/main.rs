@@ -0,0 +1,313 @@
+//! `cargo patch`
+#![warn(
+    missing_docs, missing_debug_implementations, missing_copy_implementations, trivial_casts,
+    trivial_numeric_casts, unsafe_code, unstable_features, unused_import_braces,
+    unused_qualifications
+)]
+
+extern crate cargo_metadata;
+extern crate docopt;
+#[macro_use]
+extern crate error_chain;
+#[macro_use]
+extern crate serde_derive;
+extern crate toml_edit;
+
+use std::path::{Path, PathBuf};
+use std::process;
+
+extern crate cargo_edit;
+use cargo_edit::{emit, find, print_dry_run, relative_path, Event, LocalManifest, Manifest,
+                 MessageFormat};
+
+mod args;
+use args::Args;
+
+mod errors {
+    error_chain!{
+        errors {
+            /// Tried to add a `[replace]` entry without specifying the version to replace.
+            ReplaceWithoutVersion(name: String) {
+                description("Replacing a crate requires a version")
+                display("Cannot replace `{}` without a version, e.g. `{}@1.0.0`.", name, name)
+            }
+            /// The crate is used from several sources, so it is unclear which one to patch.
+            AmbiguousSource(name: String, sources: Vec<String>) {
+                description("Crate is used from several sources")
+                display("The crate `{}` is used from several sources ({}). Specify the one to \
+                         patch with `--source`.", name, sources.join(", "))
+            }
+            /// Cargo does not accept `[patch]` and `[replace]` sections in the same manifest.
+            PatchAndReplace {
+                description("Cannot specify both `[patch]` and `[replace]`")
+                display("Cannot specify both `[patch]` and `[replace]` in the same manifest. \
+                         Remove the existing overrides first.")
+            }
+        }
+        links {
+            CargoEditLib(::cargo_edit::Error, ::cargo_edit::ErrorKind);
+            CargoMetadata(::cargo_metadata::Error, ::cargo_metadata::ErrorKind);
+        }
+        foreign_links {
+            Io(::std::io::Error);
+        }
+    }
+}
+use errors::*;
+
+static USAGE: &'static str = r"
+Usage:
+    cargo patch add <crate> --path <path> [options]
+    cargo patch add <crate> --git <uri> [--branch <branch>|--tag <tag>|--rev <rev>] [options]
+    cargo patch rm <crate> [options]
+    cargo patch list [options]
+    cargo patch (-h|--help)
+    cargo patch --version
+
+Specify what to patch the crate with:
+    --path <path>           Path to a local checkout of the crate.
+    --git <uri>             Git repository to fetch the crate from.
+    --branch <branch>       Git branch to use.
+    --tag <tag>             Git tag to use.
+    --rev <rev>             Git revision to use.
+
+Options:
+    --source <source>       The source of the crate to patch, e.g. `crates-io` or the URL of a
+                            registry or git repository. Inferred from the dependencies of the
+                            workspace if not given.
+    --replace               Add a `[replace]` entry instead of a `[patch]` entry. This requires the
+                            version to replace, e.g. `cargo patch add serde@1.0.80 --replace`.
+    --manifest-path=<path>  Path to a manifest of the workspace to patch.
+    --dry-run               Print the changes to be made without making them.
+    --diff                  Print the changes to the manifest as a unified diff. Combine with
+                            `--dry-run` to only preview them.
+    --message-format=<fmt>  Output format, `human` or `json`. [default: human]
+    -q --quiet              Do not print any output in case of success.
+    -h --help               Show this help page.
+    -V --version            Show version.
+
+Override a dependency with a local checkout or a git repository by editing the `[patch]` and
+`[replace]` sections of a Cargo.toml manifest file. In a workspace, the overrides are always
+written to the root manifest. A relative `--path` is taken relative to the manifest the command is
+run against, and rewritten to be relative to the root manifest.
+
+With `--message-format json`, one JSON object is printed per event instead: `patched` for each
+`[patch]` or `[replace]` entry added, `removed` for each one removed, and `error`.
+";
+
+/// The key Cargo uses for `[patch]` sections overriding crates from crates.io.
+const CRATES_IO: &str = "crates-io";
+
+/// The index URL of crates.io, as reported in dependency sources by `cargo metadata`.
+const CRATES_IO_INDEX: &str = "https://github.com/rust-lang/crates.io-index";
+
+/// Convert the source of a dependency, as reported by `cargo metadata`, to the key of the
+/// `[patch]` table overriding it.
+fn source_to_patch_key(source: &str) -> String {
+    let mut parts = source.splitn(2, '+');
+    match (parts.next(), parts.next()) {
+        (Some("registry"), Some(CRATES_IO_INDEX)) => CRATES_IO.to_string(),
+        // Git sources carry the branch/tag/rev as a query and the locked commit as a fragment.
+        (Some(_), Some(url)) => url.split(&['?', '#'][..]).next().unwrap_or(url).to_string(),
+        _ => source.to_string(),
+    }
+}
+
+/// Work out which source the crate comes from, by looking at how the workspace members depend on
+/// it. Crates the workspace does not depend on directly are assumed to come from crates.io.
+fn infer_source(packages: &[cargo_metadata::Package], name: &str) -> Result<String> {
+    let mut sources: Vec<String> = packages
+        .iter()
+        .flat_map(|package| package.dependencies.iter())
+        .filter(|dependency| dependency.name == name)
+        .filter_map(|dependency| dependency.source.as_ref())
+        .map(|source| source_to_patch_key(source))
+        .collect();
+    sources.sort();
+    sources.dedup();
+
+    match sources.len() {
+        0 => Ok(CRATES_IO.to_string()),
+        1 => Ok(sources.remove(0)),
+        _ => Err(ErrorKind::AmbiguousSource(name.to_string(), sources).into()),
+    }
+}
+
+/// Render a `[patch]`/`[replace]` entry on a single line.
+fn describe_entry(item: &toml_edit::Item) -> String {
+    match *item {
+        toml_edit::Item::Value(ref value) => value.to_string().trim().to_string(),
+        _ => {
+            let fields = item
+                .as_table_like()
+                .into_iter()
+                .flat_map(|t| t.iter())
+                .map(|(key, value)| format!("{} = {}", key, describe_entry(value)))
+                .collect::<Vec<_>>();
+            format!("{{ {} }}", fields.join(", "))
+        }
+    }
+}
+
+/// Add a `[patch]` or `[replace]` entry to the root manifest. A relative `--path` is relative to
+/// the directory of the manifest the command is run against, and is rebased onto that of the root
+/// manifest.
+fn handle_add(
+    args: &Args,
+    root_manifest: &mut LocalManifest,
+    metadata: &cargo_metadata::Metadata,
+) -> Result<()> {
+    let mut dependency = args.parse_dependency()?;
+    if let Some(path) = dependency.path().map(PathBuf::from) {
+        if path.is_relative() {
+            let manifest_path = find(&args.flag_manifest_path.as_ref().map(PathBuf::from))?;
+            let base = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+            let root_dir = root_manifest.path().parent().unwrap_or_else(|| Path::new("."));
+            let path = relative_path(root_dir, &base.join(path));
+            dependency = dependency.set_path(&path.to_string_lossy());
+        }
+    }
+
+    let section = if args.flag_replace {
+        vec!["replace".to_string()]
+    } else {
+        let source = match args.flag_source {
+            Some(ref source) => source.clone(),
+            None => infer_source(&metadata.packages, &args.get_crate_name()?)?,
+        };
+        vec!["patch".to_string(), source]
+    };
+
+    if root_manifest
+        .get_override_sections()
+        .iter()
+        .any(|other| other.0[0] != section[0])
+    {
+        Err(ErrorKind::PatchAndReplace)?;
+    }
+
+    if args.flag_replace {
+        root_manifest.insert_replacement(&dependency)?;
+    } else {
+        root_manifest.insert_into_table(&section, &dependency)?;
+    }
+    if let Some(patch) = root_manifest.data["patch"].as_table_mut() {
+        // Only ever print `[patch.<source>]` headers, not an empty `[patch]` one.
+        patch.set_implicit(true);
+    }
+
+    args.emit(&Event::patched(&section, &dependency.name).in_manifest(root_manifest.path()))?;
+
+    Ok(())
+}
+
+fn handle_rm(args: &Args, root_manifest: &mut LocalManifest) -> Result<()> {
+    let name = args.get_crate_name()?;
+    let source = args.flag_source.as_ref().map(|s| &s[..]);
+    let removed = root_manifest.remove_override(&name, source)?;
+
+    for section in removed {
+        args.emit(&Event::removed(&section, &name).in_manifest(root_manifest.path()))?;
+    }
+
+    Ok(())
+}
+
+fn handle_list(root_manifest: &Manifest) -> Result<()> {
+    for (section, table) in root_manifest.get_override_sections() {
+        let table = table.as_table_like().expect("Unexpected non-table");
+        for (name, entry) in table.iter() {
+            println!(
+                "{}: {} = {}",
+                section.join("."),
+                name,
+                describe_entry(entry)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_patch(args: &Args) -> Result<()> {
+    let manifest_path = args.flag_manifest_path.as_ref().map(Path::new);
+    let metadata =
+        cargo_metadata::metadata(manifest_path).chain_err(|| "Failed to get workspace metadata")?;
+    let root_manifest_path = PathBuf::from(&metadata.workspace_root).join("Cargo.toml");
+    let mut root_manifest = LocalManifest::try_new(&root_manifest_path)?;
+
+    if args.cmd_list {
+        return handle_list(&root_manifest);
+    }
+
+    if args.flag_dry_run && !args.flag_quiet && args.message_format()? == MessageFormat::Human {
+        print_dry_run()?;
+    }
+    if args.cmd_add {
+        handle_add(args, &mut root_manifest, &metadata)?;
+    } else if args.cmd_rm {
+        handle_rm(args, &mut root_manifest)?;
+    }
+
+    if args.flag_diff {
+        print!("{}", root_manifest.diff());
+    }
+    if !args.flag_dry_run {
+        root_manifest.write()?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args = docopt::Docopt::new(USAGE)
+        .and_then(|d| d.deserialize::<Args>())
+        .unwrap_or_else(|err| err.exit());
+
+    if args.flag_version {
+        println!("cargo-patch version {}", env!("CARGO_PKG_VERSION"));
+        process::exit(0);
+    }
+
+    if let Err(err) = handle_patch(&args) {
+        // An unknown format is itself reported for humans.
+        let format = args.message_format().unwrap_or(MessageFormat::Human);
+        let causes = err.iter().skip(1).map(|e| e.to_string()).collect();
+        let _ = emit(&Event::error(&err.to_string(), causes), format);
+
+        if let Some(backtrace) = err.backtrace() {
+            eprintln!("Backtrace: {:?}", backtrace);
+        }
+
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crates_io_source_to_patch_key() {
+        assert_eq!(
+            source_to_patch_key("registry+https://github.com/rust-lang/crates.io-index"),
+            "crates-io"
+        );
+    }
+
+    #[test]
+    fn git_source_to_patch_key() {
+        assert_eq!(
+            source_to_patch_key("git+https://github.com/serde-rs/serde?branch=fix#0123abcd"),
+            "https://github.com/serde-rs/serde"
+        );
+    }
+
+    #[test]
+    fn registry_source_to_patch_key() {
+        assert_eq!(
+            source_to_patch_key("registry+https://example.com/index"),
+            "https://example.com/index"
+        );
+    }
+}
@@ -1,20 +1,40 @@
 //! Handle `cargo rm` arguments
 
+use std::path::PathBuf;
+
+use cargo_edit::{emit, find_package, print_member, Event, MessageFormat};
+
+use errors::*;
+
 #[derive(Debug, Deserialize)]
 /// Docopts input args.
 pub struct Args {
-    /// Crate name
-    pub arg_crate: String,
+    /// Crate names
+    pub arg_crates: Vec<String>,
     /// dev-dependency
     pub flag_dev: bool,
     /// build-dependency
     pub flag_build: bool,
     /// `Cargo.toml` path
     pub flag_manifest_path: Option<String>,
+    /// `--package`
+    pub flag_package: Option<String>,
+    /// `--workspace`
+    pub flag_workspace: bool,
+    /// `--unused`
+    pub flag_unused: bool,
+    /// `--apply`
+    pub flag_apply: bool,
     /// `--version`
     pub flag_version: bool,
     /// '--quiet'
     pub flag_quiet: bool,
+    /// `--dry-run`
+    pub flag_dry_run: bool,
+    /// `--diff`
+    pub flag_diff: bool,
+    /// `--message-format`
+    pub flag_message_format: String,
 }
 
 impl Args {
@@ -28,17 +48,55 @@ impl Args {
             "dependencies"
         }
     }
+
+    /// Get the manifest to edit: that of the package given with `--package`, if any. Otherwise
+    /// `--manifest-path` (or the `cwd`) is searched.
+    pub fn get_manifest_path(&self) -> Result<Option<PathBuf>> {
+        let manifest_path = self.flag_manifest_path.as_ref().map(PathBuf::from);
+        Ok(match self.flag_package {
+            Some(ref name) => Some(find_package(&manifest_path, name)?),
+            None => manifest_path,
+        })
+    }
+
+    /// Get the requested output format
+    pub fn message_format(&self) -> Result<MessageFormat> {
+        Ok(self.flag_message_format.parse()?)
+    }
+
+    /// Print an event in the requested format, unless `--quiet` was given.
+    pub fn emit(&self, event: &Event) -> Result<()> {
+        if !self.flag_quiet {
+            emit(event, self.message_format()?)?;
+        }
+        Ok(())
+    }
+
+    /// Print the name of the package whose changes follow, unless `--quiet` was given.
+    pub fn print_member(&self, member: &str) -> Result<()> {
+        if !self.flag_quiet {
+            print_member(member, self.message_format()?);
+        }
+        Ok(())
+    }
 }
 
 impl Default for Args {
     fn default() -> Args {
         Args {
-            arg_crate: "demo".to_owned(),
+            arg_crates: vec!["demo".to_owned()],
             flag_dev: false,
             flag_build: false,
             flag_manifest_path: None,
+            flag_package: None,
+            flag_workspace: false,
+            flag_unused: false,
+            flag_apply: false,
             flag_version: false,
             flag_quiet: false,
+            flag_dry_run: false,
+            flag_diff: false,
+            flag_message_format: "human".to_owned(),
         }
     }
 }
@@ -5,20 +5,18 @@
     unused_qualifications
 )]
 
-extern crate atty;
 extern crate docopt;
 #[macro_use]
 extern crate error_chain;
 #[macro_use]
 extern crate serde_derive;
-extern crate termcolor;
 
-use std::io::Write;
+use std::path::PathBuf;
 use std::process;
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
 extern crate cargo_edit;
-use cargo_edit::Manifest;
+use cargo_edit::{emit, find_unused, print_dry_run, workspace_members, ChangeSet, Event,
+                 LocalManifest, Manifest, MessageFormat};
 
 mod args;
 use args::Args;
@@ -37,52 +35,201 @@ use errors::*;
 
 static USAGE: &'static str = r"
 Usage:
-    cargo rm <crate> [--dev|--build] [options]
+    cargo rm <crates>... [--dev|--build] [options]
+    cargo rm --unused [--apply] [options]
     cargo rm (-h|--help)
     cargo rm --version
 
 Options:
     -D --dev                Remove crate as development dependency.
     -B --build              Remove crate as build dependency.
+    -p --package <name>     Remove the crate from the workspace member with the given name.
+    --workspace             Remove the crate from every member of the workspace that depends on
+                            it.
+    --unused                List the dependencies that none of the package's source files refer
+                            to.
+    --apply                 Remove the unused dependencies that were found.
     --manifest-path=<path>  Path to the manifest to remove a dependency from.
+    --dry-run               Print the changes to be made without making them.
+    --diff                  Print the changes to each manifest as a unified diff. Combine with
+                            `--dry-run` to only preview them.
+    --message-format=<fmt>  Output format, `human` or `json`. [default: human]
     -q --quiet              Do not print any output in case of success.
     -h --help               Show this help page.
     -V --version            Show version.
 
-Remove a dependency from a Cargo.toml manifest file.
+Remove one or more dependencies from a Cargo.toml manifest file. If any of the given crates can not
+be found, the manifest is left unchanged.
+
+With `--unused`, the source files of the package are searched for references to each dependency:
+`extern crate`, `use` declarations, paths and macro invocations of its library name. Dependencies
+that are used without being referenced, e.g. crates that only link a native library, can be
+excluded in the manifest:
+
+    [package.metadata.cargo-edit]
+    ignore-unused = ['openssl-sys']
+
+With `--message-format json`, one JSON object is printed per event instead: `removed` for each
+dependency removed, `unused` for each one `--unused` finds without `--apply`, and `error`.
 ";
 
-fn print_msg(name: &str, section: &str) -> Result<()> {
-    let colorchoice = if atty::is(atty::Stream::Stdout) {
-        ColorChoice::Auto
-    } else {
-        ColorChoice::Never
-    };
-    let mut output = StandardStream::stdout(colorchoice);
-    output.set_color(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(true))?;
-    write!(output, "{:>12}", "Removing")?;
-    output.reset()?;
-    writeln!(output, " {} from {}", name, section)?;
+/// Remove the crates from a single manifest, adding it to `changes`.
+fn remove_from_manifest(
+    args: &Args,
+    manifest_path: &Option<PathBuf>,
+    names: &[String],
+    changes: &mut ChangeSet,
+) -> Result<()> {
+    let mut manifest = LocalManifest::find(manifest_path)?;
+    manifest.ensure_package(
+        "use `--package <name>` to remove the crate from a member of the workspace, or \
+         `--workspace` to remove it from every member that depends on it",
+    )?;
+
+    let removed = manifest
+        .remove_deps(args.get_section(), names)
+        .map_err(|err| manifest.locate_error(err))?;
+
+    let section = [args.get_section().to_owned()];
+    for name in &removed {
+        args.emit(&Event::removed(&section, name).in_manifest(manifest.path()))?;
+    }
+    changes.push(manifest);
+
     Ok(())
 }
 
-fn handle_rm(args: &Args) -> Result<()> {
-    let manifest_path = args.flag_manifest_path.as_ref().map(From::from);
-    let mut manifest = Manifest::open(&manifest_path)?;
+/// Remove the crates from every member of the workspace that depends on them. Each crate has to
+/// be found in at least one member.
+fn remove_from_workspace(args: &Args, changes: &mut ChangeSet) -> Result<()> {
+    let section = args.get_section();
+    let members = workspace_members(&args.flag_manifest_path.as_ref().map(PathBuf::from))?
+        .into_iter()
+        .map(|package| {
+            let path = PathBuf::from(package.manifest_path);
+            let manifest = Manifest::open(&Some(path.clone()))?;
+            Ok((package.name, path, manifest))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let declared_by = |manifest: &Manifest, name: &str| !manifest.data[section][name].is_none();
 
-    if !args.flag_quiet {
-        print_msg(&args.arg_crate, args.get_section())?;
+    let mut missing: Vec<String> = Vec::new();
+    for name in &args.arg_crates {
+        if !missing.contains(name)
+            && !members
+                .iter()
+                .any(|(_, _, manifest)| declared_by(manifest, name))
+        {
+            missing.push(name.clone());
+        }
+    }
+    match missing.len() {
+        0 => {}
+        1 => Err(cargo_edit::Error::from(cargo_edit::ErrorKind::NonExistentDependency(
+            missing.remove(0),
+            section.into(),
+        )))?,
+        _ => Err(cargo_edit::Error::from(cargo_edit::ErrorKind::NonExistentDependencies(
+            missing,
+            section.into(),
+        )))?,
     }
 
-    manifest
-        .remove_from_table(args.get_section(), args.arg_crate.as_ref())
-        .map_err(From::from)
-        .and_then(|_| {
-            let mut file = Manifest::find_file(&manifest_path)?;
-            manifest.write_to_file(&mut file)?;
+    for (name, path, manifest) in members {
+        let names: Vec<String> = args
+            .arg_crates
+            .iter()
+            .filter(|name| declared_by(&manifest, name))
+            .cloned()
+            .collect();
+        if names.is_empty() {
+            continue;
+        }
 
-            Ok(())
-        })
+        args.print_member(&name)?;
+        remove_from_manifest(args, &Some(path), &names, changes)?;
+    }
+
+    Ok(())
+}
+
+/// Report the dependencies of a single package that its sources do not refer to, and remove them
+/// with `--apply`, adding the manifest to `changes`. `package` is printed ahead of them, if given.
+fn remove_unused(
+    args: &Args,
+    manifest_path: &Option<PathBuf>,
+    package: Option<&str>,
+    changes: &mut ChangeSet,
+) -> Result<()> {
+    let mut manifest = LocalManifest::find(manifest_path)?;
+    manifest.ensure_package(
+        "use `--package <name>` to look for unused dependencies of a member of the workspace, or \
+         `--workspace` to look in every member",
+    )?;
+    let package_dir = manifest
+        .path()
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let unused = find_unused(&manifest, &package_dir)?;
+    if unused.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(package) = package {
+        args.print_member(package)?;
+    }
+    for dependency in &unused {
+        let event = if args.flag_apply {
+            manifest
+                .remove_from_section(&dependency.section, &dependency.name)
+                .map_err(|err| manifest.locate_error(err))?;
+            Event::removed(&dependency.section, &dependency.name)
+        } else {
+            Event::unused(&dependency.section, &dependency.name)
+        };
+        args.emit(&event.in_manifest(manifest.path()))?;
+    }
+    changes.push(manifest);
+
+    Ok(())
+}
+
+fn handle_rm(args: &Args) -> Result<()> {
+    let human = !args.flag_quiet && args.message_format()? == MessageFormat::Human;
+    if args.flag_dry_run && human {
+        print_dry_run()?;
+    }
+
+    // Nothing is written until every manifest has been edited, so that an error leaves all of
+    // them unchanged.
+    let mut changes = ChangeSet::new();
+    if args.flag_unused && args.flag_workspace {
+        for package in workspace_members(&args.flag_manifest_path.as_ref().map(PathBuf::from))? {
+            let path = PathBuf::from(package.manifest_path);
+            remove_unused(args, &Some(path), Some(&package.name), &mut changes)?;
+        }
+    } else if args.flag_unused {
+        remove_unused(args, &args.get_manifest_path()?, None, &mut changes)?;
+    } else if args.flag_workspace {
+        remove_from_workspace(args, &mut changes)?;
+    } else {
+        remove_from_manifest(
+            args,
+            &args.get_manifest_path()?,
+            &args.arg_crates,
+            &mut changes,
+        )?;
+    }
+
+    if args.flag_diff {
+        print!("{}", changes.diff());
+    }
+    if args.flag_dry_run {
+        return Ok(());
+    }
+    changes.apply()?;
+    Ok(())
 }
 
 fn main() {
@@ -96,11 +243,10 @@ fn main() {
     }
 
     if let Err(err) = handle_rm(&args) {
-        eprintln!("Command failed due to unhandled error: {}\n", err);
-
-        for e in err.iter().skip(1) {
-            eprintln!("Caused by: {}", e);
-        }
+        let causes = err.iter().skip(1).map(|e| e.to_string()).collect();
+        // Errors are reported even with `--quiet`, and an unknown format is reported for humans.
+        let format = args.message_format().unwrap_or(MessageFormat::Human);
+        let _ = emit(&Event::error(&err.to_string(), causes), format);
 
         if let Some(backtrace) = err.backtrace() {
             eprintln!("Backtrace: {:?}", backtrace);
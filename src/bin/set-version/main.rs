@@ -0,0 +1,401 @@
+//! `cargo set-version`
+#![warn(
+    missing_docs, missing_debug_implementations, missing_copy_implementations, trivial_casts,
+    trivial_numeric_casts, unsafe_code, unstable_features, unused_import_braces,
+    unused_qualifications
+)]
+
+extern crate cargo_metadata;
+extern crate docopt;
+#[macro_use]
+extern crate error_chain;
+extern crate semver;
+#[macro_use]
+extern crate serde_derive;
+extern crate toml_edit;
+
+use std::path::{Path, PathBuf};
+use std::process;
+
+extern crate cargo_edit;
+use cargo_edit::{emit, find, print_dry_run, ChangeSet, Event, LocalManifest, MessageFormat};
+
+mod errors {
+    error_chain!{
+        errors {
+            /// An unknown `--bump` level was given.
+            InvalidBumpLevel(level: String) {
+                description("Invalid bump level")
+                display("Invalid bump level `{}`. Must be one of `major`, `minor`, `patch` or \
+                         `prerelease`.", level)
+            }
+        }
+        links {
+            CargoEditLib(::cargo_edit::Error, ::cargo_edit::ErrorKind);
+            CargoMetadata(::cargo_metadata::Error, ::cargo_metadata::ErrorKind);
+        }
+        foreign_links {
+            Io(::std::io::Error);
+            SemVer(::semver::SemVerError);
+        }
+    }
+}
+use errors::*;
+
+static USAGE: &'static str = r"
+Change the version of packages, and update the requirements of the packages in the workspace that
+depend on them.
+
+Usage:
+    cargo set-version <target-version> [options]
+    cargo set-version --bump <level> [options]
+    cargo set-version (-h|--help)
+    cargo set-version --version
+
+Options:
+    --bump <level>          Increment the current version instead. Must be one of `major`,
+                            `minor`, `patch` or `prerelease`.
+    --all                   Change the version of all packages in the workspace.
+    --manifest-path=<path>  Path to the manifest of the package to change.
+    --dry-run               Print the changes to be made without making them.
+    --diff                  Print the changes to each manifest as a unified diff. Combine with
+                            `--dry-run` to only preview them.
+    --message-format=<fmt>  Output format, `human` or `json`. [default: human]
+    -q --quiet              Do not print any output in case of success.
+    -h --help               Show this help page.
+    -V --version            Show version.
+
+Path dependencies on the changed packages (including those in `[workspace.dependencies]`) that
+specify a version have their requirement updated, keeping its operator (e.g. `=` or `~`).
+Requirements with an operator that would exclude the new version, such as `<` or `>`, are replaced
+by the new version alone.
+
+A `prerelease` bump increments the last numeric part of the pre-release (`1.0.0-alpha.1` becomes
+`1.0.0-alpha.2`), or starts an `alpha.1` pre-release of the next patch version.
+
+With `--message-format json`, one JSON object is printed per event instead: `version-changed` for
+each package whose version is changed, `updated` for each requirement updated, and `error`.
+";
+
+/// Docopts input args.
+#[derive(Debug, Deserialize)]
+struct Args {
+    /// `<target-version>`
+    arg_target_version: Option<String>,
+    /// `--bump <level>`
+    flag_bump: Option<String>,
+    /// `--all`
+    flag_all: bool,
+    /// `--manifest-path`
+    flag_manifest_path: Option<String>,
+    /// `--dry-run`
+    flag_dry_run: bool,
+    /// `--diff`
+    flag_diff: bool,
+    /// `--message-format`
+    flag_message_format: String,
+    /// `--quiet`
+    flag_quiet: bool,
+    /// `--version`
+    flag_version: bool,
+}
+
+impl Args {
+    /// Get the requested output format
+    fn message_format(&self) -> Result<MessageFormat> {
+        Ok(self.flag_message_format.parse()?)
+    }
+
+    /// Print an event in the requested format, unless `--quiet` was given.
+    fn emit(&self, event: &Event) -> Result<()> {
+        if !self.flag_quiet {
+            emit(event, self.message_format()?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Which part of a version to increment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BumpLevel {
+    Major,
+    Minor,
+    Patch,
+    Prerelease,
+}
+
+impl BumpLevel {
+    fn parse(level: &str) -> Result<Self> {
+        match level {
+            "major" => Ok(BumpLevel::Major),
+            "minor" => Ok(BumpLevel::Minor),
+            "patch" => Ok(BumpLevel::Patch),
+            "prerelease" => Ok(BumpLevel::Prerelease),
+            other => Err(ErrorKind::InvalidBumpLevel(other.to_string()).into()),
+        }
+    }
+
+    /// Get the version following `version` at this level.
+    fn bump(self, version: &semver::Version) -> semver::Version {
+        let mut version = version.clone();
+        match self {
+            BumpLevel::Major => version.increment_major(),
+            BumpLevel::Minor => version.increment_minor(),
+            BumpLevel::Patch => version.increment_patch(),
+            BumpLevel::Prerelease => {
+                let next = match version.pre.last() {
+                    Some(&semver::Identifier::Numeric(n)) => Some(n + 1),
+                    _ => None,
+                };
+                match next {
+                    Some(n) => {
+                        version.pre.pop();
+                        version.pre.push(semver::Identifier::Numeric(n));
+                    }
+                    None if version.is_prerelease() => {
+                        version.pre.push(semver::Identifier::Numeric(1));
+                    }
+                    None => {
+                        version.increment_patch();
+                        version.pre = vec![
+                            semver::Identifier::AlphaNumeric("alpha".to_string()),
+                            semver::Identifier::Numeric(1),
+                        ];
+                    }
+                }
+                version.build.clear();
+            }
+        }
+        version
+    }
+}
+
+/// A package whose version is changed.
+struct VersionChange {
+    name: String,
+    manifest_path: PathBuf,
+    old: semver::Version,
+    new: semver::Version,
+}
+
+/// Replace the version in a requirement, keeping its operator, e.g. `=0.1.0` becomes `=0.2.0`.
+/// Only the operators that allow the version itself are kept: `^`, `~`, `=` and `>=`. Others, such
+/// as `<0.2` or `>0.1`, would exclude it, and requirements made of several comparators may, so
+/// these are replaced by the version alone, which Cargo reads as `^`.
+fn update_requirement(requirement: &str, version: &semver::Version) -> String {
+    if requirement.contains(',') {
+        return version.to_string();
+    }
+    let operator_len = requirement
+        .find(|c: char| !"^~=<> ".contains(c))
+        .unwrap_or(requirement.len());
+    let operator = &requirement[..operator_len];
+    match operator.trim() {
+        "" | "^" | "~" | "=" | ">=" => format!("{}{}", operator, version),
+        _ => version.to_string(),
+    }
+}
+
+/// The manifests of a workspace: the root manifest first, then those of the members.
+fn get_manifests(metadata: &cargo_metadata::Metadata) -> Result<Vec<LocalManifest>> {
+    let root_path = Path::new(&metadata.workspace_root).join("Cargo.toml");
+
+    let mut manifests = vec![LocalManifest::try_new(&root_path)?];
+    for package in &metadata.packages {
+        let path = Path::new(&package.manifest_path);
+        if path != root_path {
+            manifests.push(LocalManifest::try_new(path)?);
+        }
+    }
+
+    Ok(manifests)
+}
+
+/// Main processing function. Allows us to return a `Result` so that `main` can print pretty error
+/// messages.
+fn process(args: &Args) -> Result<()> {
+    let manifest_path = args.flag_manifest_path.as_ref().map(PathBuf::from);
+    let metadata = cargo_metadata::metadata(manifest_path.as_ref().map(Path::new))
+        .chain_err(|| "Failed to get workspace metadata")?;
+
+    let packages = if args.flag_all {
+        metadata.packages.iter().collect::<Vec<_>>()
+    } else {
+        let resolved_manifest_path = find(&manifest_path)?;
+        LocalManifest::try_new(&resolved_manifest_path)?
+            .ensure_package("use `--all` to change the version of all packages in the workspace")?;
+        let resolved_manifest_path: String = resolved_manifest_path.to_string_lossy().into();
+        vec![
+            metadata
+                .packages
+                .iter()
+                .find(|p| p.manifest_path == resolved_manifest_path)
+                .chain_err(|| {
+                    "Found virtual manifest, but this command requires running against an \
+                     actual package in this workspace. Try adding `--all`."
+                })?,
+        ]
+    };
+
+    let bump = match args.flag_bump {
+        Some(ref level) => Some(BumpLevel::parse(level)?),
+        None => None,
+    };
+    let target = match args.arg_target_version {
+        Some(ref version) => Some(semver::Version::parse(version)?),
+        None => None,
+    };
+
+    let changes = packages
+        .into_iter()
+        .map(|package| {
+            let old = semver::Version::parse(&package.version)?;
+            let new = match (bump, &target) {
+                (Some(level), _) => level.bump(&old),
+                (None, Some(version)) => version.clone(),
+                (None, None) => unreachable!("docopt requires a version or `--bump`"),
+            };
+            Ok(VersionChange {
+                name: package.name.clone(),
+                manifest_path: PathBuf::from(&package.manifest_path),
+                old,
+                new,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut manifests = get_manifests(&metadata)?;
+
+    if args.flag_dry_run && !args.flag_quiet && args.message_format()? == MessageFormat::Human {
+        print_dry_run()?;
+    }
+
+    // Change the versions of the packages themselves. A version inherited from the workspace
+    // (`version.workspace = true`) is changed in `[workspace.package]` instead.
+    for change in &changes {
+        let event = Event::version_changed(&change.name, &change.old, &change.new);
+        args.emit(&event.in_manifest(&change.manifest_path))?;
+
+        let index = manifests
+            .iter()
+            .position(|manifest| manifest.path() == change.manifest_path)
+            .expect("all packages have been loaded");
+        let data = &manifests[index].data;
+        let package = if data["package"].is_none() {
+            "project"
+        } else {
+            "package"
+        };
+        if data[package]["version"]["workspace"].as_bool() == Some(true) {
+            manifests[0].set_workspace_package_version(&change.new)?;
+        } else {
+            manifests[index].set_package_version(&change.new)?;
+        }
+    }
+
+    // Update the requirements of path dependencies on the changed packages.
+    for manifest in &mut manifests {
+        for (section, table) in manifest.get_sections() {
+            let table = table.as_table_like().expect("Unexpected non-table");
+            let updates = table
+                .iter()
+                .filter(|&(_, item)| !item["path"].is_none())
+                .filter_map(|(name, item)| {
+                    let package = item["package"].as_str().unwrap_or(name);
+                    let requirement = item["version"].as_str()?;
+                    changes
+                        .iter()
+                        .find(|change| change.name == package)
+                        .map(|change| {
+                            let new = update_requirement(requirement, &change.new);
+                            (name.to_string(), requirement.to_string(), new)
+                        })
+                })
+                .collect::<Vec<_>>();
+
+            for (name, old, new) in updates {
+                let event = Event::updated(&section, &name, &old, &new);
+                args.emit(&event.in_manifest(manifest.path()))?;
+                manifest.get_table(&section)?[&name]["version"] = toml_edit::value(new);
+            }
+        }
+    }
+
+    let mut changes = ChangeSet::new();
+    for manifest in manifests {
+        changes.push(manifest);
+    }
+    if args.flag_diff {
+        print!("{}", changes.diff());
+    }
+    if !args.flag_dry_run {
+        changes.apply()?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args = docopt::Docopt::new(USAGE)
+        .and_then(|d| d.deserialize::<Args>())
+        .unwrap_or_else(|err| err.exit());
+
+    if args.flag_version {
+        println!("cargo-set-version version {}", env!("CARGO_PKG_VERSION"));
+        process::exit(0);
+    }
+
+    if let Err(err) = process(&args) {
+        // An unknown format is itself reported for humans.
+        let format = args.message_format().unwrap_or(MessageFormat::Human);
+        let causes = err.iter().skip(1).map(|e| e.to_string()).collect();
+        let _ = emit(&Event::error(&err.to_string(), causes), format);
+
+        if let Some(backtrace) = err.backtrace() {
+            eprintln!("Backtrace: {:?}", backtrace);
+        }
+
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bump(level: BumpLevel, version: &str) -> String {
+        level
+            .bump(&semver::Version::parse(version).unwrap())
+            .to_string()
+    }
+
+    #[test]
+    fn bump_levels() {
+        assert_eq!(bump(BumpLevel::Major, "0.3.1"), "1.0.0");
+        assert_eq!(bump(BumpLevel::Minor, "0.3.1"), "0.4.0");
+        assert_eq!(bump(BumpLevel::Patch, "0.3.1-beta.2"), "0.3.2");
+        assert_eq!(bump(BumpLevel::Prerelease, "0.3.1"), "0.3.2-alpha.1");
+        assert_eq!(bump(BumpLevel::Prerelease, "0.3.2-alpha.1"), "0.3.2-alpha.2");
+        assert_eq!(bump(BumpLevel::Prerelease, "0.3.2-rc"), "0.3.2-rc.1");
+    }
+
+    #[test]
+    fn keeps_requirement_operator() {
+        let version = semver::Version::parse("0.2.0").unwrap();
+        assert_eq!(update_requirement("0.1", &version), "0.2.0");
+        assert_eq!(update_requirement("=0.1.0", &version), "=0.2.0");
+        assert_eq!(update_requirement("~ 0.1.0", &version), "~ 0.2.0");
+        assert_eq!(update_requirement(">=0.1, <0.2", &version), "0.2.0");
+        assert_eq!(update_requirement(">= 0.1.0", &version), ">= 0.2.0");
+    }
+
+    #[test]
+    fn replaces_excluding_operators() {
+        let version = semver::Version::parse("1.0.0").unwrap();
+        for requirement in &["<0.2", "<=0.2", ">0.2", "< 0.2"] {
+            let updated = update_requirement(requirement, &version);
+            assert_eq!(updated, "1.0.0");
+            assert!(semver::VersionReq::parse(&updated).unwrap().matches(&version));
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! `cargo upgrade --interactive`: choose the upgrades to apply.
+
+use std::io::{BufRead, Write};
+
+use cargo_edit::RequirementChange;
+
+use errors::*;
+use AvailableUpgrade;
+
+/// The upgrade of one crate, from all the requirements on it that would change.
+#[derive(Debug, PartialEq, Eq)]
+struct Candidate<'a> {
+    name: &'a str,
+    /// The current requirements, without duplicates
+    old: Vec<&'a str>,
+    new: &'a str,
+    /// Whether any of the current requirements does not allow the new one
+    breaking: bool,
+}
+
+/// Group the available upgrades by crate, in the order they were found.
+fn candidates<'a>(available: &'a [AvailableUpgrade]) -> Vec<Candidate<'a>> {
+    let mut candidates: Vec<Candidate> = Vec::new();
+    for upgrade in available {
+        let breaking = upgrade.change == RequirementChange::Breaking;
+        match candidates.iter_mut().find(|c| c.name == upgrade.name) {
+            Some(candidate) => {
+                if !candidate.old.contains(&upgrade.old.as_str()) {
+                    candidate.old.push(&upgrade.old);
+                }
+                candidate.breaking |= breaking;
+            }
+            None => candidates.push(Candidate {
+                name: &upgrade.name,
+                old: vec![&upgrade.old],
+                new: &upgrade.new,
+                breaking,
+            }),
+        }
+    }
+    candidates
+}
+
+/// Ask whether to apply each upgrade, reading one answer per line from `input`: `y` to apply it,
+/// `n` (or nothing) to skip it, `a` to apply it and all the remaining ones, `q` to skip it and all
+/// the remaining ones. The end of `input` skips the remaining upgrades as well.
+///
+/// Returns the names of the crates to upgrade.
+pub fn select_upgrades<R: BufRead, W: Write>(
+    available: &[AvailableUpgrade],
+    input: &mut R,
+    output: &mut W,
+) -> Result<Vec<String>> {
+    let mut selected = Vec::new();
+    let mut all = false;
+
+    for candidate in candidates(available) {
+        if all {
+            selected.push(candidate.name.to_string());
+            continue;
+        }
+
+        loop {
+            write!(
+                output,
+                "Upgrade {} {} -> {}{}? [y,n,a,q] ",
+                candidate.name,
+                candidate.old.join(", "),
+                candidate.new,
+                if candidate.breaking {
+                    " (incompatible)"
+                } else {
+                    ""
+                }
+            ).chain_err(|| "Failed to write prompt")?;
+            output.flush().chain_err(|| "Failed to write prompt")?;
+
+            let mut answer = String::new();
+            if input
+                .read_line(&mut answer)
+                .chain_err(|| "Failed to read answer")?
+                == 0
+            {
+                // Nobody is left to answer.
+                writeln!(output).chain_err(|| "Failed to write prompt")?;
+                return Ok(selected);
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => selected.push(candidate.name.to_string()),
+                "n" | "no" | "" => {}
+                "a" | "all" => {
+                    selected.push(candidate.name.to_string());
+                    all = true;
+                }
+                "q" | "quit" => return Ok(selected),
+                _ => {
+                    writeln!(output, "Please answer y, n, a or q.")
+                        .chain_err(|| "Failed to write prompt")?;
+                    continue;
+                }
+            }
+            break;
+        }
+    }
+
+    Ok(selected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn available(name: &str, old: &str, new: &str, change: RequirementChange) -> AvailableUpgrade {
+        AvailableUpgrade {
+            member: "foo".to_string(),
+            manifest_path: PathBuf::from("Cargo.toml"),
+            section: vec!["dependencies".to_string()],
+            name: name.to_string(),
+            old: old.to_string(),
+            new: new.to_string(),
+            change,
+        }
+    }
+
+    fn select(available: &[AvailableUpgrade], answers: &str) -> (Vec<String>, String) {
+        let mut output = Vec::new();
+        let selected = select_upgrades(available, &mut answers.as_bytes(), &mut output).unwrap();
+        (selected, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn grouped_by_crate() {
+        let upgrades = vec![
+            available("docopt", "0.8", "1.0.0", RequirementChange::Breaking),
+            available("serde", "1.0", "1.0.80", RequirementChange::Compatible),
+            available("docopt", "1.0", "1.0.0", RequirementChange::Compatible),
+        ];
+        let (selected, output) = select(&upgrades, "n\ny\n");
+        assert_eq!(selected, vec!["serde"]);
+        assert_eq!(
+            output,
+            "Upgrade docopt 0.8, 1.0 -> 1.0.0 (incompatible)? [y,n,a,q] \
+             Upgrade serde 1.0 -> 1.0.80? [y,n,a,q] "
+        );
+    }
+
+    #[test]
+    fn answers() {
+        let upgrades = vec![
+            available("a", "1", "2.0.0", RequirementChange::Breaking),
+            available("b", "1", "2.0.0", RequirementChange::Breaking),
+            available("c", "1", "2.0.0", RequirementChange::Breaking),
+        ];
+        assert_eq!(select(&upgrades, "y\na\n").0, vec!["a", "b", "c"]);
+        assert_eq!(select(&upgrades, "\nq\n").0, Vec::<String>::new());
+        assert_eq!(select(&upgrades, "Y\n").0, vec!["a"]);
+
+        let (selected, output) = select(&upgrades, "maybe\nyes\n");
+        assert_eq!(selected, vec!["a"]);
+        assert!(output.contains("Please answer y, n, a or q."));
+    }
+}
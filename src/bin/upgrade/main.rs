@@ -9,20 +9,23 @@ extern crate cargo_metadata;
 extern crate docopt;
 #[macro_use]
 extern crate error_chain;
+extern crate semver;
 #[macro_use]
 extern crate serde_derive;
 extern crate toml_edit;
 
-use std::collections::HashMap;
-use std::io::Write;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
 use std::path::{Path, PathBuf};
 use std::process;
 
 extern crate cargo_edit;
-use cargo_edit::{find, get_latest_dependency, CrateName, Dependency, LocalManifest};
-
-extern crate termcolor;
-use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, WriteColor};
+use cargo_edit::{
+    emit, find, get_available_versions, get_latest_dependency, highest_matching_version,
+    print_dry_run, print_member, read_lockfile, registry_requirement, requirement_at_version,
+    requirement_change, unify_requirements, ChangeSet, CrateName, Dependency, Event,
+    LocalManifest, MessageFormat, RequirementChange, Unification,
+};
 
 mod errors {
     error_chain!{
@@ -34,7 +37,10 @@ mod errors {
 }
 use errors::*;
 
-static USAGE: &'static str = r"
+mod interactive;
+use interactive::select_upgrades;
+
+static USAGE: &'static str = r#"
 Upgrade dependencies as specified in the local manifest file (i.e. Cargo.toml).
 
 Usage:
@@ -48,11 +54,30 @@ Options:
     --allow-prerelease      Include prerelease versions when fetching from crates.io (e.g.
                             '0.6.0-alpha'). Defaults to false.
     --dry-run               Print changes to be made without making them. Defaults to false.
+    --diff                  Print the changes to each manifest as a unified diff. Combine with
+                            `--dry-run` to only preview them.
+    --unify                 Do not look for new versions. Instead, raise the requirements of all
+                            packages in the workspace to the highest version any of them currently
+                            requires, keeping their operators, if they are compatible with it.
+    --to-lockfile           Do not look for new versions. Instead, raise each requirement to the
+                            version recorded in the lock file (Cargo.lock).
+    --update-lock           Update Cargo.lock after writing the manifests, and undo the changes if
+                            the new requirements can not be resolved. Has no effect with `--unify`
+                            and `--to-lockfile`, as the lock file already allows their changes.
+    --check                 Do not write anything. Instead, report the dependencies that can be
+                            upgraded, and exit with status 2 if there are any.
+    --compatible            Only upgrade to the latest version the current requirements already
+                            allow, e.g. `1.2` to `1.9` but not to `2.0`.
+    --incompatible          Only upgrade dependencies whose latest version the current
+                            requirements do not allow, e.g. `1.2` to `2.0`.
+    -i --interactive        Ask which of the upgrades found to apply.
+    --message-format=<fmt>  Output format, `human` or `json`. [default: human]
     -h --help               Show this help page.
     -V --version            Show version.
 
 This command differs from `cargo update`, which updates the dependency versions recorded in the
-local lock file (Cargo.lock).
+local lock file (Cargo.lock). To bring the requirements in the manifests up to the versions
+`cargo update` has locked, use `--to-lockfile`.
 
 If `<dependency>`(s) are provided, only the specified dependencies will be upgraded. The version to
 upgrade to for each can be specified with e.g. `docopt@0.8.0` or `serde@>=0.9,<2.0`.
@@ -62,7 +87,38 @@ supported. Git/path dependencies will be ignored.
 
 All packages in the workspace will be upgraded if the `--all` flag is supplied. The `--all` flag may
 be supplied in the presence of a virtual manifest.
-";
+
+Dependencies inherited from the workspace (`foo = { workspace = true }`) are upgraded where they are
+defined, in the `[workspace.dependencies]` table of the workspace root manifest.
+
+With `--unify`, all packages in the workspace are considered, and nothing is fetched from crates.io.
+A requirement is only replaced by one with a higher minimum version that it already allows, e.g.
+`1.0.80` becomes `1.0.100` if another package requires that. Crates required at semver
+incompatible versions (e.g. `0.9` and `1.0`), or pinned to an exact version (`=1.0.80`) by a
+package, are skipped.
+
+With `--to-lockfile`, nothing is fetched from crates.io either. The operator of each requirement is
+kept, e.g. `~1.2` becomes `~1.2.5` if version 1.2.5 is locked. Requirements the locked version does
+not match, or that are ranges such as `>=1.0, <2.0`, are left alone.
+
+With `--compatible`, each requirement is raised to the latest version on crates.io it matches,
+keeping its operator like `--to-lockfile` does, e.g. `^1.2` becomes `^1.9.0`. Versions can not be
+given with `<dependency>@<version>` then. With `--incompatible`, only the requirements the latest
+version does not match are upgraded, and the others are left alone.
+
+With `--check`, the versions to upgrade to are looked up as usual, and compared to the current
+requirements. Use it in CI to fail when dependencies fall behind, optionally only on compatible or
+incompatible upgrades.
+
+With `--interactive`, each crate that can be upgraded is shown with its current and new
+requirements, and whether the upgrade is incompatible with the current requirement. Answer `y` to
+upgrade it, `n` to skip it, `a` to upgrade it and all the remaining ones, or `q` to skip the rest.
+Questions are asked on stderr, and answers read from stdin one per line.
+
+With `--message-format json`, one JSON object is printed per event instead, e.g.
+`{"manifest_path":"...","section":["dependencies"],"crate":"docopt","event":"upgraded","old":"0.8",
+"new":"1.0"}`. The events are `upgraded`, `skipped`, `outdated`, `locked` and `error`.
+"#;
 
 /// Docopts input args.
 #[derive(Debug, Deserialize)]
@@ -77,30 +133,116 @@ struct Args {
     flag_allow_prerelease: bool,
     /// `--dry-run`
     flag_dry_run: bool,
+    /// `--diff`
+    flag_diff: bool,
+    /// `--unify`
+    flag_unify: bool,
+    /// `--to-lockfile`
+    flag_to_lockfile: bool,
+    /// `--update-lock`
+    flag_update_lock: bool,
+    /// `--check`
+    flag_check: bool,
+    /// `--compatible`
+    flag_compatible: bool,
+    /// `--incompatible`
+    flag_incompatible: bool,
+    /// `--interactive`
+    flag_interactive: bool,
+    /// `--message-format`
+    flag_message_format: String,
     /// `--version`
     flag_version: bool,
 }
 
+impl Args {
+    /// Get the requested output format
+    fn message_format(&self) -> Result<MessageFormat> {
+        Ok(self.flag_message_format.parse()?)
+    }
+}
+
+/// A dependency that `cargo upgrade` would upgrade.
+struct AvailableUpgrade {
+    /// The package declaring the dependency, or `workspace` for the workspace root manifest
+    member: String,
+    manifest_path: PathBuf,
+    /// Path to the section containing the dependency
+    section: Vec<String>,
+    name: String,
+    /// The current requirement
+    old: String,
+    /// The requirement it would be upgraded to
+    new: String,
+    change: RequirementChange,
+}
+
+impl AvailableUpgrade {
+    /// The `outdated` event reporting the upgrade.
+    fn event(&self) -> Event {
+        Event::outdated(&self.section, &self.name, &self.old, &self.new, self.change)
+            .in_manifest(&self.manifest_path)
+    }
+}
+
 /// A collection of manifests.
-struct Manifests(Vec<(LocalManifest, cargo_metadata::Package)>);
+struct Manifests {
+    /// The manifests of the packages to upgrade
+    packages: Vec<(LocalManifest, cargo_metadata::Package)>,
+    /// The workspace root manifest, if it defines `[workspace.dependencies]` and is not one of
+    /// the packages above. The shared definitions there are upgraded once, on behalf of all the
+    /// members inheriting them.
+    workspace_root: Option<LocalManifest>,
+    /// The lock file of the workspace
+    lock_file: PathBuf,
+}
+
+/// Get the workspace root manifest, if it has a `[workspace.dependencies]` table and is not the
+/// manifest of one of `packages`.
+fn get_workspace_root(
+    metadata: &cargo_metadata::Metadata,
+    packages: &[(LocalManifest, cargo_metadata::Package)],
+) -> Result<Option<LocalManifest>> {
+    let root_manifest_path = Path::new(&metadata.workspace_root).join("Cargo.toml");
+    if packages
+        .iter()
+        .any(|(_, package)| Path::new(&package.manifest_path) == root_manifest_path)
+    {
+        return Ok(None);
+    }
+
+    let root = LocalManifest::try_new(&root_manifest_path)?;
+    Ok(if root.data["workspace"]["dependencies"].is_table_like() {
+        Some(root)
+    } else {
+        None
+    })
+}
 
 impl Manifests {
     /// Get all manifests in the workspace.
     fn get_all(manifest_path: &Option<String>) -> Result<Self> {
         let manifest_path = manifest_path.clone().map(PathBuf::from);
 
-        cargo_metadata::metadata(manifest_path.as_ref().map(Path::new))
-            .chain_err(|| "Failed to get workspace metadata")?
+        let metadata = cargo_metadata::metadata(manifest_path.as_ref().map(Path::new))
+            .chain_err(|| "Failed to get workspace metadata")?;
+        let packages = metadata
             .packages
-            .into_iter()
+            .iter()
             .map(|package| {
                 Ok((
                     LocalManifest::try_new(Path::new(&package.manifest_path))?,
-                    package,
+                    package.to_owned(),
                 ))
             })
-            .collect::<Result<Vec<_>>>()
-            .map(Manifests)
+            .collect::<Result<Vec<_>>>()?;
+        let workspace_root = get_workspace_root(&metadata, &packages)?;
+
+        Ok(Manifests {
+            packages,
+            workspace_root,
+            lock_file: Path::new(&metadata.workspace_root).join("Cargo.lock"),
+        })
     }
 
     /// Get the manifest specified by the manifest path. Try to make an educated guess if no path is
@@ -110,11 +252,12 @@ impl Manifests {
         let resolved_manifest_path: String = find(&manifest_path)?.to_string_lossy().into();
 
         let manifest = LocalManifest::find(&manifest_path)?;
+        manifest.ensure_package("use `--all` to upgrade all packages in the workspace")?;
 
-        let packages = cargo_metadata::metadata(manifest_path.as_ref().map(Path::new))
-            .chain_err(|| "Invalid manifest")?
-            .packages;
-        let package = packages
+        let metadata = cargo_metadata::metadata(manifest_path.as_ref().map(Path::new))
+            .chain_err(|| "Invalid manifest")?;
+        let package = metadata
+            .packages
             .iter()
             .find(|p| p.manifest_path == resolved_manifest_path)
             // If we have successfully got metadata, but our manifest path does not correspond to a
@@ -122,7 +265,14 @@ impl Manifests {
             .chain_err(|| "Found virtual manifest, but this command requires running against an \
                            actual package in this workspace. Try adding `--all`.")?;
 
-        Ok(Manifests(vec![(manifest, package.to_owned())]))
+        let packages = vec![(manifest, package.to_owned())];
+        let workspace_root = get_workspace_root(&metadata, &packages)?;
+
+        Ok(Manifests {
+            packages,
+            workspace_root,
+            lock_file: Path::new(&metadata.workspace_root).join("Cargo.lock"),
+        })
     }
 
     /// Get the the combined set of dependencies to upgrade. If the user has specified
@@ -141,7 +291,7 @@ impl Manifests {
         Ok(DesiredUpgrades(if only_update.is_empty() {
             // User hasn't asked for any specific dependencies to be upgraded, so upgrade all the
             // dependencies.
-            self.0
+            self.packages
                 .iter()
                 .flat_map(|&(_, ref package)| package.dependencies.clone())
                 .filter(is_version_dep)
@@ -164,38 +314,321 @@ impl Manifests {
         }))
     }
 
-    /// Upgrade the manifests on disk following the previously-determined upgrade schema.
-    fn upgrade(self, upgraded_deps: &ActualUpgrades, dry_run: bool) -> Result<()> {
-        if dry_run {
-            let bufwtr = BufferWriter::stdout(ColorChoice::Always);
-            let mut buffer = bufwtr.buffer();
-            buffer
-                .set_color(ColorSpec::new().set_fg(Some(Color::Cyan)).set_bold(true))
-                .chain_err(|| "Failed to set output colour")?;
-            write!(&mut buffer, "Starting dry run. ")
-                .chain_err(|| "Failed to write dry run message")?;
-            buffer
-                .set_color(&ColorSpec::new())
-                .chain_err(|| "Failed to clear output colour")?;
-            writeln!(&mut buffer, "Changes will not be saved.")
-                .chain_err(|| "Failed to write dry run message")?;
-            bufwtr
-                .print(&buffer)
-                .chain_err(|| "Failed to print dry run message")?;
+    /// Upgrade the manifests in memory following the previously-determined upgrade schema.
+    fn upgrade(self, upgraded_deps: &ActualUpgrades, format: MessageFormat) -> Result<ChangeSet> {
+        let mut changes = ChangeSet::new();
+        let root_path = self
+            .workspace_root
+            .as_ref()
+            .map(|root| root.path().to_path_buf());
+
+        for (member, mut manifest) in self.into_named() {
+            let mut events = Vec::new();
+            for (name, version) in &upgraded_deps.0 {
+                events.extend(manifest.upgrade(&Dependency::new(name).set_version(version))?);
+            }
+
+            // Every package is listed, but the workspace root only if its shared definitions
+            // change.
+            if !events.is_empty() || Some(manifest.path()) != root_path.as_deref() {
+                print_member(&member, format);
+            }
+            for event in events {
+                emit(&event, format)?;
+            }
+            changes.push(manifest);
+        }
+
+        Ok(changes)
+    }
+
+    /// All the manifests, with the name to report them by.
+    fn into_named(self) -> Vec<(String, LocalManifest)> {
+        self.workspace_root
+            .map(|root| ("workspace".to_string(), root))
+            .into_iter()
+            .chain(
+                self.packages
+                    .into_iter()
+                    .map(|(manifest, package)| (package.name, manifest)),
+            )
+            .collect()
+    }
+
+    /// All the manifests, with the name to report them by, without consuming them.
+    fn named(&self) -> Vec<(&str, &LocalManifest)> {
+        self.workspace_root
+            .iter()
+            .map(|root| ("workspace", root))
+            .chain(
+                self.packages
+                    .iter()
+                    .map(|(manifest, package)| (package.name.as_str(), manifest)),
+            )
+            .collect()
+    }
+
+    /// Compare the requirements in the manifests to the upgraded ones, without changing anything.
+    fn check(&self, upgraded_deps: &ActualUpgrades) -> Vec<AvailableUpgrade> {
+        let mut available = Vec::new();
+        for (member, manifest) in self.named() {
+            for (section, table) in manifest.get_sections() {
+                let table = table.as_table_like().expect("Unexpected non-table");
+                for (name, item) in table.iter() {
+                    let (requirement, new_requirement) =
+                        match (registry_requirement(item), upgraded_deps.0.get(name)) {
+                            (Some(requirement), Some(new_requirement)) => {
+                                (requirement, new_requirement)
+                            }
+                            _ => continue,
+                        };
+                    let change = requirement_change(requirement, new_requirement);
+                    if change != RequirementChange::Unchanged {
+                        available.push(AvailableUpgrade {
+                            member: member.to_string(),
+                            manifest_path: manifest.path().to_path_buf(),
+                            section: section.clone(),
+                            name: name.to_string(),
+                            old: requirement.to_string(),
+                            new: new_requirement.clone(),
+                            change,
+                        });
+                    }
+                }
+            }
+        }
+        available
+    }
+
+    /// Find the latest version on crates.io each requirement on a crates.io dependency matches, and
+    /// the requirement with the same operator at that version. Only the requirements that would be
+    /// raised are returned, without changing anything.
+    fn check_compatible(
+        &self,
+        only_update: &[String],
+        allow_prerelease: bool,
+    ) -> Result<Vec<AvailableUpgrade>> {
+        // The versions of each crate, fetched once however many requirements there are on it.
+        let mut versions: HashMap<String, Vec<semver::Version>> = HashMap::new();
+
+        let mut available = Vec::new();
+        for (member, manifest) in self.named() {
+            for (section, table) in manifest.get_sections() {
+                let table = table.as_table_like().expect("Unexpected non-table");
+                for (name, item) in table.iter() {
+                    let requirement = match registry_requirement(item) {
+                        Some(requirement) if is_selected(only_update, name) => requirement,
+                        _ => continue,
+                    };
+                    if !versions.contains_key(name) {
+                        let found = get_available_versions(name, allow_prerelease)
+                            .chain_err(|| "Failed to get new version")?;
+                        versions.insert(name.to_string(), found);
+                    }
+                    let new_requirement = highest_matching_version(requirement, &versions[name])
+                        .and_then(|version| requirement_at_version(requirement, version));
+                    match new_requirement {
+                        Some(new_requirement)
+                            if requirement_change(requirement, &new_requirement)
+                                != RequirementChange::Unchanged =>
+                        {
+                            available.push(AvailableUpgrade {
+                                member: member.to_string(),
+                                manifest_path: manifest.path().to_path_buf(),
+                                section: section.clone(),
+                                name: name.to_string(),
+                                old: requirement.to_string(),
+                                new: new_requirement,
+                                change: RequirementChange::Compatible,
+                            })
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(available)
+    }
+
+    /// Apply the given upgrades to the manifests in memory, each to its own entry only. Only the
+    /// manifests that change are reported.
+    fn apply(self, upgrades: &[AvailableUpgrade], format: MessageFormat) -> Result<ChangeSet> {
+        let mut changed = ChangeSet::new();
+        for (member, mut manifest) in self.into_named() {
+            let changes = upgrades
+                .iter()
+                .filter(|upgrade| upgrade.manifest_path == manifest.path())
+                .collect::<Vec<_>>();
+            if changes.is_empty() {
+                continue;
+            }
+
+            print_member(&member, format);
+            for upgrade in changes {
+                let dependency = Dependency::new(&upgrade.name).set_version(&upgrade.new);
+                if let Some(event) =
+                    manifest.update_table_entry(&upgrade.section, &dependency)?
+                {
+                    emit(&event.in_manifest(manifest.path()), format)?;
+                }
+            }
+            changed.push(manifest);
         }
 
-        for (mut manifest, package) in self.0 {
-            println!("{}:", package.name);
+        Ok(changed)
+    }
 
-            for (name, version) in &upgraded_deps.0 {
-                manifest.upgrade(&Dependency::new(name).set_version(version), dry_run)?;
+    /// Raise the requirements on each crates.io dependency to the highest minimum version found in
+    /// any of the manifests, keeping their operators, without looking for newer versions. Only the
+    /// manifests that change are reported.
+    /// Crates whose requirements are not semver compatible, or that one manifest pins to an exact
+    /// version, are reported as skipped.
+    fn unify(self, only_update: &[String], format: MessageFormat) -> Result<ChangeSet> {
+        let manifests = self.into_named();
+
+        // Every requirement on each crate, in all the manifests.
+        let mut requirements: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (_, manifest) in &manifests {
+            for (_, table) in manifest.get_sections() {
+                let table = table.as_table_like().expect("Unexpected non-table");
+                for (name, item) in table.iter() {
+                    if let Some(requirement) = registry_requirement(item) {
+                        requirements
+                            .entry(name.to_string())
+                            .or_default()
+                            .push(requirement.to_string());
+                    }
+                }
+            }
+        }
+
+        let mut unified = BTreeMap::new();
+        for (name, found) in requirements
+            .iter()
+            .filter(|(name, _)| is_selected(only_update, name))
+        {
+            match unify_requirements(found.iter().map(String::as_str)) {
+                Some(Unification::Unified(version)) => {
+                    unified.insert(name.clone(), version);
+                }
+                Some(Unification::Conflict(conflicting)) => {
+                    let reason = format!("incompatible requirements {}", conflicting.join(", "));
+                    emit(&Event::skipped(&[], name, &reason), format)?;
+                }
+                None => {}
+            }
+        }
+
+        let mut changed = ChangeSet::new();
+        for (member, mut manifest) in manifests {
+            let mut changes = Vec::new();
+            for (table_path, table) in manifest.get_sections() {
+                let table = table.as_table_like().expect("Unexpected non-table");
+                for (name, item) in table.iter() {
+                    let requirement = registry_requirement(item);
+                    let (requirement, version) = match (requirement, unified.get(name)) {
+                        (Some(requirement), Some(version)) => (requirement, version),
+                        _ => continue,
+                    };
+                    // Requirements that can not be raised with their operator already allow the
+                    // version, and those that already require it are left as they are written.
+                    match requirement_at_version(requirement, version) {
+                        Some(ref new_requirement)
+                            if requirement_change(requirement, new_requirement)
+                                != RequirementChange::Unchanged =>
+                        {
+                            changes.push((
+                                table_path.clone(),
+                                Dependency::new(name).set_version(new_requirement),
+                            ))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            if changes.is_empty() {
+                continue;
+            }
+
+            print_member(&member, format);
+            for (table_path, dependency) in changes {
+                if let Some(event) = manifest.update_table_entry(&table_path, &dependency)? {
+                    emit(&event.in_manifest(manifest.path()), format)?;
+                }
+            }
+            changed.push(manifest);
+        }
+
+        Ok(changed)
+    }
+
+    /// Raise the requirement on each crates.io dependency to the version recorded in the lock
+    /// file, keeping its operator. Only the manifests that change are reported.
+    fn match_lockfile(self, only_update: &[String], format: MessageFormat) -> Result<ChangeSet> {
+        if !self.lock_file.exists() {
+            return Err(format!(
+                "No lock file found at {}. Run `cargo generate-lockfile` first.",
+                self.lock_file.display()
+            ).into());
+        }
+        let locked = read_lockfile(&self.lock_file)?;
+
+        let mut changed = ChangeSet::new();
+        for (member, mut manifest) in self.into_named() {
+            let mut changes = Vec::new();
+            for (table_path, table) in manifest.get_sections() {
+                let table = table.as_table_like().expect("Unexpected non-table");
+                for (name, item) in table.iter() {
+                    let requirement = match registry_requirement(item) {
+                        Some(requirement) if is_selected(only_update, name) => requirement,
+                        _ => continue,
+                    };
+                    // Several versions of a crate may be locked. The requirement resolved to the
+                    // highest one it matches.
+                    let new_requirement = locked
+                        .iter()
+                        .filter(|package| package.name == name && package.is_registry())
+                        .filter_map(|package| {
+                            requirement_at_version(requirement, &package.version)
+                                .map(|new_requirement| (&package.version, new_requirement))
+                        })
+                        .max_by(|a, b| a.0.cmp(b.0))
+                        .map(|(_, new_requirement)| new_requirement);
+                    match new_requirement {
+                        Some(ref new_requirement) if new_requirement != requirement => changes
+                            .push((
+                                table_path.clone(),
+                                Dependency::new(name).set_version(new_requirement),
+                            )),
+                        _ => {}
+                    }
+                }
+            }
+            if changes.is_empty() {
+                continue;
             }
+
+            print_member(&member, format);
+            for (table_path, dependency) in changes {
+                if let Some(event) = manifest.update_table_entry(&table_path, &dependency)? {
+                    emit(&event.in_manifest(manifest.path()), format)?;
+                }
+            }
+            changed.push(manifest);
         }
 
-        Ok(())
+        Ok(changed)
     }
 }
 
+/// Whether the dependency `name` is one of the ones to upgrade. An empty list selects all of them.
+fn is_selected(only_update: &[String], name: &str) -> bool {
+    only_update.is_empty()
+        || only_update
+            .iter()
+            .any(|only| only.split('@').next() == Some(name))
+}
+
 /// The set of dependencies to be upgraded, alongside desired versions, if specified by the user.
 struct DesiredUpgrades(HashMap<String, Option<String>>);
 
@@ -231,29 +664,168 @@ impl DesiredUpgrades {
     }
 }
 
-/// Main processing function. Allows us to return a `Result` so that `main` can print pretty error
-/// messages.
-fn process(args: Args) -> Result<()> {
+/// Report the upgrades available. Returns the exit code: 2 if any upgrades were reported, 0
+/// otherwise.
+fn report_upgrades(available: &[AvailableUpgrade], format: MessageFormat) -> Result<i32> {
+    let mut member = None;
+    for upgrade in available {
+        if member != Some(&upgrade.member) {
+            print_member(&upgrade.member, format);
+            member = Some(&upgrade.member);
+        }
+        emit(&upgrade.event(), format)?;
+    }
+
+    if available.is_empty() && format == MessageFormat::Human {
+        println!("All dependencies are up to date.");
+    }
+    Ok(if available.is_empty() { 0 } else { 2 })
+}
+
+/// Main processing function. Returns the exit code on success, so that `--check` can signal its
+/// result to CI.
+fn process(args: Args) -> Result<i32> {
+    let format = args.message_format()?;
     let Args {
         arg_dependency,
         flag_manifest_path,
         flag_all,
         flag_allow_prerelease,
         flag_dry_run,
+        flag_diff,
+        flag_unify,
+        flag_to_lockfile,
+        flag_update_lock,
+        flag_check,
+        flag_compatible,
+        flag_incompatible,
+        flag_interactive,
         ..
     } = args;
 
-    let manifests = if flag_all {
-        Manifests::get_all(&flag_manifest_path)
+    if flag_unify && flag_to_lockfile {
+        return Err("`--unify` and `--to-lockfile` can not be combined".into());
+    }
+    if flag_check && (flag_unify || flag_to_lockfile) {
+        return Err("`--check` can not be combined with `--unify` or `--to-lockfile`".into());
+    }
+    if flag_interactive && (flag_check || flag_unify || flag_to_lockfile) {
+        return Err(
+            "`--interactive` can not be combined with `--check`, `--unify` or `--to-lockfile`"
+                .into(),
+        );
+    }
+    if flag_compatible && flag_incompatible {
+        return Err("`--compatible` and `--incompatible` can not be combined".into());
+    }
+    if (flag_compatible || flag_incompatible) && (flag_unify || flag_to_lockfile) {
+        return Err(
+            "`--compatible` and `--incompatible` can not be combined with `--unify` or \
+             `--to-lockfile`"
+                .into(),
+        );
+    }
+    if flag_compatible && arg_dependency.iter().any(|dependency| dependency.contains('@')) {
+        return Err("`--compatible` can not be combined with `<dependency>@<version>`".into());
+    }
+
+    // The crates to update in the lock file. With `--to-lockfile` and `--unify`, the lock file
+    // already allows the new requirements, so no crate has to be updated in it.
+    let mut upgraded = Vec::new();
+    let mut changes = if flag_to_lockfile {
+        let manifests = if flag_all {
+            Manifests::get_all(&flag_manifest_path)
+        } else {
+            Manifests::get_local_one(&flag_manifest_path)
+        }?;
+        if flag_dry_run && format == MessageFormat::Human {
+            print_dry_run()?;
+        }
+        manifests.match_lockfile(&arg_dependency, format)?
+    } else if flag_unify {
+        let manifests = Manifests::get_all(&flag_manifest_path)?;
+        if flag_dry_run && format == MessageFormat::Human {
+            print_dry_run()?;
+        }
+        manifests.unify(&arg_dependency, format)?
     } else {
-        Manifests::get_local_one(&flag_manifest_path)
-    }?;
+        let manifests = if flag_all {
+            Manifests::get_all(&flag_manifest_path)
+        } else {
+            Manifests::get_local_one(&flag_manifest_path)
+        }?;
+
+        // With `--compatible`, each requirement is upgraded on its own. Otherwise, all the
+        // requirements on a crate are upgraded to the same version.
+        let mut upgraded_dependencies = None;
+        let mut available = if flag_compatible {
+            manifests.check_compatible(&arg_dependency, flag_allow_prerelease)?
+        } else {
+            let existing_dependencies = manifests.get_dependencies(arg_dependency)?;
+            let actual = existing_dependencies.get_upgraded(flag_allow_prerelease)?;
+            let available = manifests.check(&actual);
+            upgraded_dependencies = Some(actual);
+            available
+        };
+        if flag_incompatible {
+            available.retain(|upgrade| upgrade.change == RequirementChange::Breaking);
+        }
+
+        if flag_check {
+            return report_upgrades(&available, format);
+        }
+
+        if flag_interactive {
+            if available.is_empty() {
+                if format == MessageFormat::Human {
+                    println!("All dependencies are up to date.");
+                }
+                return Ok(0);
+            }
+            let stdin = io::stdin();
+            let selected = select_upgrades(&available, &mut stdin.lock(), &mut io::stderr())?;
+            available.retain(|upgrade| selected.contains(&upgrade.name));
+            if let Some(ref mut upgraded_dependencies) = upgraded_dependencies {
+                upgraded_dependencies
+                    .0
+                    .retain(|name, _| selected.contains(name));
+            }
+        }
 
-    let existing_dependencies = manifests.get_dependencies(arg_dependency)?;
+        if flag_dry_run && format == MessageFormat::Human {
+            print_dry_run()?;
+        }
+        match upgraded_dependencies {
+            Some(ref upgraded_dependencies) if !flag_incompatible => {
+                upgraded.extend(upgraded_dependencies.0.keys().cloned());
+                manifests.upgrade(upgraded_dependencies, format)?
+            }
+            _ => {
+                for upgrade in &available {
+                    if !upgraded.contains(&upgrade.name) {
+                        upgraded.push(upgrade.name.clone());
+                    }
+                }
+                manifests.apply(&available, format)?
+            }
+        }
+    };
 
-    let upgraded_dependencies = existing_dependencies.get_upgraded(flag_allow_prerelease)?;
+    if flag_diff {
+        print!("{}", changes.diff());
+    }
+    // Nothing has been written so far: the manifests are only changed on disk here.
+    if !flag_dry_run {
+        changes.apply()?;
+
+        if flag_update_lock {
+            for change in changes.update_lockfile(&upgraded)? {
+                emit(&Event::locked(&change), format)?;
+            }
+        }
+    }
 
-    manifests.upgrade(&upgraded_dependencies, flag_dry_run)
+    Ok(0)
 }
 
 fn main() {
@@ -266,17 +838,19 @@ fn main() {
         process::exit(0);
     }
 
-    if let Err(err) = process(args) {
-        eprintln!("Command failed due to unhandled error: {}\n", err);
+    // An unknown format is itself reported for humans.
+    let format = args.message_format().unwrap_or(MessageFormat::Human);
+    match process(args) {
+        Ok(code) => process::exit(code),
+        Err(err) => {
+            let causes = err.iter().skip(1).map(|e| e.to_string()).collect();
+            let _ = emit(&Event::error(&err.to_string(), causes), format);
 
-        for e in err.iter().skip(1) {
-            eprintln!("Caused by: {}", e);
-        }
+            if let Some(backtrace) = err.backtrace() {
+                eprintln!("Backtrace: {:?}", backtrace);
+            }
 
-        if let Some(backtrace) = err.backtrace() {
-            eprintln!("Backtrace: {:?}", backtrace);
+            process::exit(1);
         }
-
-        process::exit(1);
     }
 }
@@ -6,7 +6,25 @@ enum DependencySource {
         version: Option<String>,
         path: Option<String>,
     },
-    Git(String),
+    Git {
+        repo: String,
+        reference: Option<GitReference>,
+        /// The version requirement Cargo checks the crate in the repository against
+        version: Option<String>,
+    },
+    /// Inherited from `[workspace.dependencies]` in the workspace root manifest
+    Workspace,
+}
+
+/// A reference to a commit in a git repository
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub enum GitReference {
+    /// The head of a branch
+    Branch(String),
+    /// A tag
+    Tag(String),
+    /// A specific revision
+    Rev(String),
 }
 
 /// A dependency handled by Cargo
@@ -15,6 +33,12 @@ pub struct Dependency {
     /// The name of the dependency (as it is set in its `Cargo.toml` and known to crates.io)
     pub name: String,
     optional: bool,
+    default_features: bool,
+    features: Option<Vec<String>>,
+    /// The name the dependency is known by in the manifest, if it differs from `name`
+    rename: Option<String>,
+    /// The name of the registry to fetch the dependency from, if not crates.io
+    registry: Option<String>,
     source: DependencySource,
 }
 
@@ -23,6 +47,10 @@ impl Default for Dependency {
         Dependency {
             name: "".into(),
             optional: false,
+            default_features: true,
+            features: None,
+            rename: None,
+            registry: None,
             source: DependencySource::Version {
                 version: None,
                 path: None,
@@ -41,34 +69,64 @@ impl Dependency {
     }
 
     /// Set dependency to a given version
+    ///
+    /// The `path` or git repository of the dependency, if any, is kept.
     pub fn set_version(mut self, version: &str) -> Dependency {
-        let old_source = self.source;
-        let old_path = match old_source {
-            DependencySource::Version { path, .. } => path,
-            _ => None,
-        };
-        self.source = DependencySource::Version {
-            version: Some(version.into()),
-            path: old_path,
+        self.source = match self.source {
+            DependencySource::Version { path, .. } => DependencySource::Version {
+                version: Some(version.into()),
+                path,
+            },
+            DependencySource::Git {
+                repo, reference, ..
+            } => DependencySource::Git {
+                repo,
+                reference,
+                version: Some(version.into()),
+            },
+            DependencySource::Workspace => DependencySource::Version {
+                version: Some(version.into()),
+                path: None,
+            },
         };
         self
     }
 
     /// Set dependency to a given repository
+    ///
+    /// The version requirement of the dependency, if any, is kept.
     pub fn set_git(mut self, repo: &str) -> Dependency {
-        self.source = DependencySource::Git(repo.into());
+        self.source = DependencySource::Git {
+            repo: repo.into(),
+            reference: None,
+            version: self.version().map(String::from),
+        };
+        self
+    }
+
+    /// Pin a git dependency to a given branch, tag or revision
+    ///
+    /// This has no effect if the dependency is not a git dependency.
+    pub fn set_git_reference(mut self, new_reference: GitReference) -> Dependency {
+        if let DependencySource::Git {
+            ref mut reference, ..
+        } = self.source
+        {
+            *reference = Some(new_reference);
+        }
+        self
+    }
+
+    /// Inherit the dependency from the workspace root manifest, i.e. `workspace = true`
+    pub fn set_workspace(mut self) -> Dependency {
+        self.source = DependencySource::Workspace;
         self
     }
 
     /// Set dependency to a given path
     pub fn set_path(mut self, path: &str) -> Dependency {
-        let old_source = self.source;
-        let old_version = match old_source {
-            DependencySource::Version { version, .. } => version,
-            _ => None,
-        };
         self.source = DependencySource::Version {
-            version: old_version,
+            version: self.version().map(String::from),
             path: Some(path.into()),
         };
         self
@@ -80,38 +138,181 @@ impl Dependency {
         self
     }
 
-    /// Get version of dependency
-    pub fn version(&self) -> Option<&str> {
+    /// Set whether the default features of the dependency are enabled
+    pub fn set_default_features(mut self, default_features: bool) -> Dependency {
+        self.default_features = default_features;
+        self
+    }
+
+    /// Set the features of the dependency to enable
+    pub fn set_features(mut self, features: Vec<String>) -> Dependency {
+        self.features = Some(features);
+        self
+    }
+
+    /// Declare the dependency under another name, i.e. `rename = { package = "name" }`
+    pub fn set_rename(mut self, rename: &str) -> Dependency {
+        self.rename = Some(rename.into());
+        self
+    }
+
+    /// Fetch the dependency from the registry with the given name instead of crates.io
+    pub fn set_registry(mut self, registry: &str) -> Dependency {
+        self.registry = Some(registry.into());
+        self
+    }
+
+    /// The key of the dependency in the manifest: its new name if it is renamed, otherwise its
+    /// name
+    pub fn toml_key(&self) -> &str {
+        self.rename.as_ref().unwrap_or(&self.name)
+    }
+
+    /// Get the path of the dependency, if any
+    pub fn path(&self) -> Option<&str> {
         if let DependencySource::Version {
-            version: Some(ref version),
+            path: Some(ref path),
             ..
         } = self.source
         {
-            Some(version)
+            Some(path)
         } else {
             None
         }
     }
 
+    /// Parse a dependency entry of a manifest, where `key` is its key in the dependency table.
+    /// Returns `None` if the entry is neither a version requirement nor a table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate cargo_edit;
+    /// # extern crate toml_edit;
+    /// # fn main() {
+    ///     use cargo_edit::{Dependency, Manifest};
+    ///
+    ///     let manifest: Manifest = r#"
+    /// [dependencies]
+    /// json = { version = "1.0", package = "serde_json", default-features = false }
+    /// "#.parse().unwrap();
+    ///     let item = &manifest.data["dependencies"]["json"];
+    ///     let dep = Dependency::from_toml("json", item).unwrap();
+    ///     assert_eq!(dep.name, "serde_json");
+    ///     assert_eq!(dep.toml_key(), "json");
+    ///     assert_eq!(dep.version(), Some("1.0"));
+    /// # }
+    /// ```
+    pub fn from_toml(key: &str, item: &toml_edit::Item) -> Option<Dependency> {
+        if let Some(version) = item.as_str() {
+            return Some(Dependency::new(key).set_version(version));
+        }
+        if !item.is_table_like() {
+            return None;
+        }
+
+        let string = |key: &str| item[key].as_str().map(|value| value.to_string());
+        let mut dependency = match string("package") {
+            Some(package) => Dependency::new(&package).set_rename(key),
+            None => Dependency::new(key),
+        };
+        dependency.source = if item["workspace"].as_bool().unwrap_or(false) {
+            DependencySource::Workspace
+        } else if let Some(repo) = string("git") {
+            DependencySource::Git {
+                repo,
+                reference: string("branch")
+                    .map(GitReference::Branch)
+                    .or_else(|| string("tag").map(GitReference::Tag))
+                    .or_else(|| string("rev").map(GitReference::Rev)),
+                version: string("version"),
+            }
+        } else {
+            DependencySource::Version {
+                version: string("version"),
+                path: string("path"),
+            }
+        };
+        dependency.registry = string("registry");
+        dependency.optional = item["optional"].as_bool().unwrap_or(false);
+        dependency.default_features = item["default-features"]
+            .as_bool()
+            .or_else(|| item["default_features"].as_bool())
+            .unwrap_or(true);
+        dependency.features = item["features"].as_array().map(|features| {
+            features
+                .iter()
+                .filter_map(|feature| feature.as_str())
+                .map(|feature| feature.to_string())
+                .collect()
+        });
+        Some(dependency)
+    }
+
+    /// Get version of dependency
+    pub fn version(&self) -> Option<&str> {
+        match self.source {
+            DependencySource::Version {
+                version: Some(ref version),
+                ..
+            }
+            | DependencySource::Git {
+                version: Some(ref version),
+                ..
+            } => Some(version),
+            _ => None,
+        }
+    }
+
+    /// Get the name of the registry the dependency is fetched from, if not crates.io
+    pub fn registry(&self) -> Option<&str> {
+        self.registry.as_deref()
+    }
+
+    /// Whether the dependency is optional
+    pub fn is_optional(&self) -> bool {
+        self.optional
+    }
+
+    /// Get the features of the dependency to enable, if any are set
+    pub fn features(&self) -> Option<&[String]> {
+        self.features.as_deref()
+    }
+
+    /// Whether the dependency is inherited from the workspace
+    pub fn is_workspace(&self) -> bool {
+        self.source == DependencySource::Workspace
+    }
+
     /// Convert dependency to TOML
     ///
-    /// Returns a tuple with the dependency's name and either the version as a `String`
+    /// Returns a tuple with the dependency's key and either the version as a `String`
     /// or the path/git repository as an `InlineTable`.
-    /// (If the dependency is set as `optional`, an `InlineTable` is returned in any case.)
+    /// (If the dependency is set as `optional`, or has features or a new name, an `InlineTable` is
+    /// returned in any case.)
     pub fn to_toml(&self) -> (String, toml_edit::Item) {
-        let data: toml_edit::Item = match (self.optional, self.source.clone()) {
+        let short = !self.optional
+            && self.default_features
+            && self.features.is_none()
+            && self.rename.is_none()
+            && self.registry.is_none();
+        let data: toml_edit::Item = match (short, self.source.clone()) {
             // Extra short when version flag only
             (
-                false,
+                true,
                 DependencySource::Version {
                     version: Some(v),
                     path: None,
                 },
             ) => toml_edit::value(v),
             // Other cases are represented as an inline table
-            (optional, source) => {
+            (_, source) => {
                 let mut data = toml_edit::InlineTable::default();
 
+                if self.rename.is_some() {
+                    data.get_or_insert("package", self.name.as_str());
+                }
+
                 match source {
                     DependencySource::Version { version, path } => {
                         if let Some(v) = version {
@@ -121,12 +322,47 @@ impl Dependency {
                             data.get_or_insert("path", p);
                         }
                     }
-                    DependencySource::Git(v) => {
-                        data.get_or_insert("git", v);
+                    DependencySource::Git {
+                        repo,
+                        reference,
+                        version,
+                    } => {
+                        if let Some(v) = version {
+                            data.get_or_insert("version", v);
+                        }
+                        data.get_or_insert("git", repo);
+                        match reference {
+                            Some(GitReference::Branch(b)) => {
+                                data.get_or_insert("branch", b);
+                            }
+                            Some(GitReference::Tag(t)) => {
+                                data.get_or_insert("tag", t);
+                            }
+                            Some(GitReference::Rev(r)) => {
+                                data.get_or_insert("rev", r);
+                            }
+                            None => {}
+                        }
+                    }
+                    DependencySource::Workspace => {
+                        data.get_or_insert("workspace", true);
+                    }
+                }
+                if let Some(ref registry) = self.registry {
+                    data.get_or_insert("registry", registry.as_str());
+                }
+                if !self.default_features {
+                    data.get_or_insert("default-features", false);
+                }
+                if let Some(ref features) = self.features {
+                    let mut array = toml_edit::Array::default();
+                    for feature in features {
+                        array.push(feature.as_str());
                     }
+                    data.get_or_insert("features", array);
                 }
                 if self.optional {
-                    data.get_or_insert("optional", optional);
+                    data.get_or_insert("optional", true);
                 }
 
                 data.fmt();
@@ -134,6 +370,6 @@ impl Dependency {
             }
         };
 
-        (self.name.clone(), data)
+        (self.toml_key().to_string(), data)
     }
 }
@@ -0,0 +1,183 @@
+//! Point at the place in a manifest an error comes from.
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use toml_edit;
+
+use errors::*;
+
+/// A problem at a known place in a manifest, rendered like the diagnostics of `rustc`:
+///
+/// ```text
+/// Manifest is not valid TOML
+///  --> Cargo.toml:6:7
+///   |
+/// 6 | key = invalid-value
+///   |       ^
+///   = note: Unexpected `i`
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// What went wrong
+    pub message: String,
+    /// Path to the manifest
+    pub path: PathBuf,
+    /// Line of the problem, starting at 1
+    pub line: usize,
+    /// Column of the problem, starting at 1
+    pub column: usize,
+    /// The contents of the line
+    pub source_line: String,
+    /// Further details about the problem
+    pub notes: Vec<String>,
+    /// What to do about the problem
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    /// Create a diagnostic pointing at a line and column of `source`, the contents of the manifest
+    /// at `path`. Lines and columns start at 1.
+    pub fn new(message: &str, path: &Path, source: &str, line: usize, column: usize) -> Self {
+        Diagnostic {
+            message: message.to_string(),
+            path: path.to_path_buf(),
+            line,
+            column,
+            source_line: source
+                .lines()
+                .nth(line.saturating_sub(1))
+                .unwrap_or("")
+                .to_string(),
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    /// Add a note with further details.
+    pub fn add_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    /// Suggest what to do about the problem.
+    pub fn set_help(mut self, help: &str) -> Self {
+        self.help = Some(help.to_string());
+        self
+    }
+
+    /// Create a diagnostic from a TOML parse error, which reports the position as part of its
+    /// message.
+    pub fn from_toml_error(
+        path: &Path,
+        source: &str,
+        error: &toml_edit::TomlError,
+    ) -> Option<Self> {
+        let message = error.to_string();
+        let mut lines = message.lines();
+        let position = lines.next()?.trim_start_matches("TOML parse error at line ");
+        let mut position = position.splitn(2, ", column ");
+        let line = position.next()?.parse().ok()?;
+        let column = position.next()?.parse().ok()?;
+
+        let diagnostic = Diagnostic::new("Manifest is not valid TOML", path, source, line, column);
+        // Skip the snippet rendered by `toml_edit`, and the parser's internal context.
+        Some(
+            lines
+                .skip(3)
+                .filter(|line| line.starts_with("Unexpected") || line.starts_with("Expected"))
+                .fold(diagnostic, |diagnostic, note| diagnostic.add_note(note)),
+        )
+    }
+}
+
+/// Find the line on which a table starts, e.g. `[dev-dependencies]`. Tables defined by dotted keys
+/// or inline tables are not found.
+pub fn find_table_header(source: &str, table: &str) -> Option<usize> {
+    let header = format!("[{}]", table);
+    source
+        .lines()
+        .position(|line| {
+            let line = line.split('#').next().unwrap_or("");
+            line.chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>() == header
+        })
+        .map(|index| index + 1)
+}
+
+impl From<Diagnostic> for Error {
+    fn from(diagnostic: Diagnostic) -> Self {
+        ErrorKind::Diagnostic(Box::new(diagnostic)).into()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f, "{}", self.message)?;
+        writeln!(
+            f,
+            "{}--> {}:{}:{}",
+            gutter,
+            self.path.display(),
+            self.line,
+            self.column
+        )?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.source_line)?;
+        write!(
+            f,
+            "{} | {}^",
+            gutter,
+            " ".repeat(self.column.saturating_sub(1))
+        )?;
+        for note in &self.notes {
+            write!(f, "\n{} = note: {}", gutter, note)?;
+        }
+        if let Some(ref help) = self.help {
+            write!(f, "\n{} = help: {}", gutter, help)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        let source = "[package]\nname = \"foo\"\n\n[dependencies]\nkey = invalid-value\n";
+        let diagnostic = Diagnostic::new("Oops", Path::new("Cargo.toml"), source, 5, 7)
+            .add_note("Unexpected `i`")
+            .set_help("quote the value");
+        assert_eq!(
+            diagnostic.to_string(),
+            "Oops
+ --> Cargo.toml:5:7
+  |
+5 | key = invalid-value
+  |       ^
+  = note: Unexpected `i`
+  = help: quote the value"
+        );
+    }
+
+    #[test]
+    fn from_toml_error() {
+        let source = "[package]\nname = \"foo\"\nkey = invalid-value\n";
+        let error = source.parse::<toml_edit::Document>().unwrap_err();
+        let diagnostic =
+            Diagnostic::from_toml_error(Path::new("Cargo.toml"), source, &error).unwrap();
+        assert_eq!((diagnostic.line, diagnostic.column), (3, 7));
+        assert_eq!(diagnostic.source_line, "key = invalid-value");
+        assert_eq!(diagnostic.notes[0], "Unexpected `i`");
+    }
+
+    #[test]
+    fn table_header() {
+        let source = "[package]\n\n[ dev-dependencies ] # tests\nfoo = \"1\"\n";
+        assert_eq!(find_table_header(source, "dev-dependencies"), Some(3));
+        assert_eq!(find_table_header(source, "dependencies"), None);
+    }
+}
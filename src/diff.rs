@@ -0,0 +1,213 @@
+//! Render the changes to a manifest as a unified diff.
+
+/// Number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Compute the shortest edit script turning `old` into `new`, using their longest common
+/// subsequence. Each entry is the operation and the line it applies to.
+fn edit_script<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    // `lcs[i][j]` is the length of the longest common subsequence of `old[i..]` and `new[j..]`.
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut script = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            script.push((Op::Equal, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Removed lines come before the ones replacing them.
+            script.push((Op::Delete, old[i]));
+            i += 1;
+        } else {
+            script.push((Op::Insert, new[j]));
+            j += 1;
+        }
+    }
+    script
+}
+
+/// Format a hunk range, leaving out the length if it is 1 like `diff -u` does.
+fn range(start: usize, len: usize) -> String {
+    // An empty range starts at the line before it.
+    let start = if len == 0 { start } else { start + 1 };
+    if len == 1 {
+        start.to_string()
+    } else {
+        format!("{},{}", start, len)
+    }
+}
+
+/// Render the differences between two texts as a unified diff, with `path` as the name of both
+/// files. Returns an empty string if the texts are the same. A missing final newline is marked
+/// like `diff -u` does, and texts that differ only in their line endings are reported as such.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate cargo_edit;
+/// # fn main() {
+///     use cargo_edit::unified_diff;
+///
+///     let diff = unified_diff("[dependencies]\nserde = \"1.0\"\n",
+///                             "[dependencies]\nserde = \"1.1\"\n",
+///                             "Cargo.toml");
+///     assert_eq!(diff, "--- Cargo.toml\n+++ Cargo.toml\n@@ -1,2 +1,2 @@\n [dependencies]\n\
+///                       -serde = \"1.0\"\n+serde = \"1.1\"\n");
+/// # }
+/// ```
+pub fn unified_diff(old: &str, new: &str, path: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", path, path);
+    if old.lines().eq(new.lines()) && old.ends_with('\n') == new.ends_with('\n') {
+        // The lines would look the same, so say what changed instead.
+        diff.push_str("Only line endings differ\n");
+        return diff;
+    }
+
+    // Lines keep their line endings, so that a changed ending or final newline is a change.
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let script = edit_script(&old_lines, &new_lines);
+    let changes: Vec<usize> = script
+        .iter()
+        .enumerate()
+        .filter(|&(_, &(op, _))| op != Op::Equal)
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut first = 0;
+    while first < changes.len() {
+        // Changes closer together than twice the context end up in the same hunk.
+        let mut last = first;
+        while last + 1 < changes.len() && changes[last + 1] - changes[last] <= 2 * CONTEXT {
+            last += 1;
+        }
+        let start = changes[first].saturating_sub(CONTEXT);
+        let end = (changes[last] + CONTEXT + 1).min(script.len());
+
+        // The number of old (or new) lines in a part of the script.
+        let consumed = |ops: &[(Op, &str)], skip: Op| {
+            ops.iter().filter(|&&(op, _)| op != skip).count()
+        };
+        let old_start = consumed(&script[..start], Op::Insert);
+        let new_start = consumed(&script[..start], Op::Delete);
+        let hunk = &script[start..end];
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, consumed(hunk, Op::Insert)),
+            range(new_start, consumed(hunk, Op::Delete))
+        ));
+        for &(op, line) in hunk {
+            let marker = match op {
+                Op::Equal => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+            diff.push(marker);
+            match line.strip_suffix('\n') {
+                Some(line) => diff.push_str(line.strip_suffix('\r').unwrap_or(line)),
+                None => diff.push_str(&format!("{}\n\\ No newline at end of file", line)),
+            }
+            diff.push('\n');
+        }
+
+        first = last + 1;
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_changes() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "Cargo.toml"), "");
+    }
+
+    #[test]
+    fn separate_hunks() {
+        let old = (1..20).map(|i| format!("{}\n", i)).collect::<String>();
+        let new = (1..20)
+            .map(|i| match i {
+                2 => "two\n".to_string(),
+                15 => "15\n15.5\n".to_string(),
+                18 => String::new(),
+                i => format!("{}\n", i),
+            })
+            .collect::<String>();
+        assert_eq!(
+            unified_diff(&old, &new, "Cargo.toml"),
+            "--- Cargo.toml
++++ Cargo.toml
+@@ -1,5 +1,5 @@
+ 1
+-2
++two
+ 3
+ 4
+ 5
+@@ -13,7 +13,7 @@
+ 13
+ 14
+ 15
++15.5
+ 16
+ 17
+-18
+ 19
+"
+        );
+    }
+
+    #[test]
+    fn final_newline() {
+        assert_eq!(
+            unified_diff("a\nb", "a\nb\n", "Cargo.toml"),
+            "--- Cargo.toml\n+++ Cargo.toml\n@@ -1,2 +1,2 @@\n \
+             a\n-b\n\\ No newline at end of file\n+b\n"
+        );
+        assert_eq!(
+            unified_diff("a\n", "a", "Cargo.toml"),
+            "--- Cargo.toml\n+++ Cargo.toml\n@@ -1 +1 @@\n-a\n+a\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn line_endings() {
+        assert_eq!(
+            unified_diff("a\nb\n", "a\r\nb\r\n", "Cargo.toml"),
+            "--- Cargo.toml\n+++ Cargo.toml\nOnly line endings differ\n"
+        );
+    }
+
+    #[test]
+    fn insert_into_empty() {
+        assert_eq!(
+            unified_diff("", "[dependencies]\n", "Cargo.toml"),
+            "--- Cargo.toml\n+++ Cargo.toml\n@@ -0,0 +1 @@\n+[dependencies]\n"
+        );
+    }
+}
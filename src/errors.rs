@@ -37,6 +37,21 @@ error_chain!{
             description("Found virtual manifest, but this command requires running against an \
                          actual package in this workspace.")
         }
+        /// Edited a table that only the manifest of a package can have in a virtual manifest
+        VirtualManifestTable(table: String) {
+            description("table not allowed in a virtual manifest")
+            display("Found virtual manifest, but `{}` can only be edited in the manifest of an \
+                     actual package in this workspace.", table)
+        }
+        /// Found a manifest that is neither a package nor a workspace root
+        InvalidRootManifest {
+            description("Cargo.toml missing expected `package`, `project` or `workspace` fields")
+        }
+        /// No member of the workspace has the given name.
+        NonExistentPackage(name: String) {
+            description("non existent package")
+            display("The package `{}` could not be found in the workspace.", name)
+        }
         /// The TOML table could not be found.
         NonExistentTable(table: String) {
             description("non existent table")
@@ -47,5 +62,44 @@ error_chain!{
             description("non existent dependency")
             display("The dependency `{}` could not be found in `{}`.", name, table)
         }
+        /// A problem at a known place in a manifest.
+        Diagnostic(diagnostic: Box<::diagnostic::Diagnostic>) {
+            description("problem in manifest")
+            display("{}", diagnostic)
+        }
+        /// `cargo update` could not resolve the dependencies.
+        LockfileUpdateFailed(output: String) {
+            description("failed to update lock file")
+            display("`cargo update` failed:\n{}", output)
+        }
+        /// A manifest was changed by someone else while it was being edited.
+        ManifestChanged(path: String) {
+            description("manifest changed since it was read")
+            display("{} was changed by another process while it was being edited. Run the \
+                     command again to apply the edits on top of those changes.", path)
+        }
+        /// The journal has no entries.
+        NothingToUndo {
+            description("Nothing to undo")
+        }
+        /// Files written by a command have been changed since, so it can not be undone.
+        ChangedSinceEdit(operation: String, paths: Vec<String>) {
+            description("files changed since edit")
+            display("Can not undo `{}`, as these files have been changed since:\n{}",
+                    operation, paths.iter().map(|path| format!("  {}", path))
+                        .collect::<Vec<_>>().join("\n"))
+        }
+        /// Several dependencies could not be found.
+        NonExistentDependencies(names: Vec<String>, table: String) {
+            description("non existent dependencies")
+            display("The dependencies {} could not be found in `{}`.",
+                    names.iter().map(|name| format!("`{}`", name)).collect::<Vec<_>>().join(", "),
+                    table)
+        }
+        /// An unknown `--message-format` was given.
+        UnknownMessageFormat(format: String) {
+            description("Unknown message format")
+            display("Unknown message format `{}`. Must be one of `human` or `json`.", format)
+        }
     }
 }
@@ -0,0 +1,509 @@
+//! What the commands report: one event per dependency they add, remove, upgrade or leave alone.
+//!
+//! The same events are printed as coloured text for humans, or serialized as one JSON object per
+//! line for `--message-format json`.
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use atty;
+use semver;
+use serde_json;
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+use dependency::Dependency;
+use errors::*;
+use lockfile::LockChange;
+use requirement::RequirementChange;
+
+/// How to print events, as given with `--message-format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// Coloured text for humans
+    Human,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for MessageFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            other => Err(ErrorKind::UnknownMessageFormat(other.to_string()).into()),
+        }
+    }
+}
+
+/// Something a command did, or decided not to do.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Event {
+    /// The manifest the event is about, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest_path: Option<PathBuf>,
+    /// Path to the section containing the dependency, e.g. `["target", "cfg(unix)",
+    /// "dependencies"]`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub section: Vec<String>,
+    /// The name of the dependency, as it is written in the manifest
+    #[serde(rename = "crate", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// What happened
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+/// The kinds of events, with what is specific to each.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum EventKind {
+    /// A dependency was added, or merged into an existing one
+    Added {
+        /// The version requirement, if any
+        version: Option<String>,
+        /// Whether the dependency is optional
+        optional: bool,
+        /// Whether the dependency is inherited from `[workspace.dependencies]`
+        inherited: bool,
+    },
+    /// A dependency was removed
+    Removed,
+    /// The requirement on a dependency was raised
+    Upgraded {
+        /// The requirement before
+        old: String,
+        /// The requirement now
+        new: String,
+    },
+    /// A dependency could be upgraded, but was left alone
+    Outdated {
+        /// The current requirement
+        old: String,
+        /// The requirement it would be upgraded to
+        new: String,
+        /// Whether the current requirement allows the new one
+        change: RequirementChange,
+    },
+    /// None of the source files of the package refer to a dependency
+    Unused,
+    /// A dependency was left alone
+    Skipped {
+        /// Why it was left alone
+        reason: String,
+    },
+    /// A `[patch]` or `[replace]` entry was added for a crate
+    Patched,
+    /// The version of a package was changed
+    VersionChanged {
+        /// The version before
+        old: semver::Version,
+        /// The version now
+        new: semver::Version,
+    },
+    /// The requirement on a path dependency was updated to the changed version of its package
+    Updated {
+        /// The requirement before
+        old: String,
+        /// The requirement now
+        new: String,
+    },
+    /// A dependency declared by several members was moved to `[workspace.dependencies]`
+    Hoisted {
+        /// The requirement in `[workspace.dependencies]`
+        requirement: String,
+        /// The members that declared the dependency
+        members: Vec<String>,
+        /// The requirements the members declared, in the same order
+        requirements: Vec<String>,
+    },
+    /// The last command was undone
+    Undone {
+        /// The command, as it was run
+        operation: String,
+    },
+    /// A manifest was restored by `cargo edit undo`
+    Restored,
+    /// The version of a package in the lock file changed
+    Locked {
+        /// The version locked before, if any
+        old: Option<semver::Version>,
+        /// The version locked now, if any
+        new: Option<semver::Version>,
+    },
+    /// The command failed
+    Error {
+        /// What went wrong
+        message: String,
+        /// The errors that caused it, outermost first
+        causes: Vec<String>,
+    },
+}
+
+impl Event {
+    fn new(section: &[String], name: &str, kind: EventKind) -> Self {
+        Event {
+            manifest_path: None,
+            section: section.to_vec(),
+            name: Some(name.to_string()),
+            kind,
+        }
+    }
+
+    /// A dependency was added to `section`.
+    pub fn added(section: &[String], dep: &Dependency, optional: bool, inherited: bool) -> Self {
+        let kind = EventKind::Added {
+            version: dep.version().map(String::from),
+            optional,
+            inherited,
+        };
+        Event::new(section, &dep.name, kind)
+    }
+
+    /// A dependency was removed from `section`.
+    pub fn removed(section: &[String], name: &str) -> Self {
+        Event::new(section, name, EventKind::Removed)
+    }
+
+    /// The requirement on a dependency was raised from `old` to `new`.
+    pub fn upgraded(section: &[String], name: &str, old: &str, new: &str) -> Self {
+        let kind = EventKind::Upgraded {
+            old: old.to_string(),
+            new: new.to_string(),
+        };
+        Event::new(section, name, kind)
+    }
+
+    /// The requirement on a dependency could be raised from `old` to `new`.
+    pub fn outdated(
+        section: &[String],
+        name: &str,
+        old: &str,
+        new: &str,
+        change: RequirementChange,
+    ) -> Self {
+        let kind = EventKind::Outdated {
+            old: old.to_string(),
+            new: new.to_string(),
+            change,
+        };
+        Event::new(section, name, kind)
+    }
+
+    /// No source file refers to a dependency.
+    pub fn unused(section: &[String], name: &str) -> Self {
+        Event::new(section, name, EventKind::Unused)
+    }
+
+    /// A dependency was left alone, for the given reason. The section is empty if it was left alone
+    /// everywhere.
+    pub fn skipped(section: &[String], name: &str, reason: &str) -> Self {
+        let kind = EventKind::Skipped {
+            reason: reason.to_string(),
+        };
+        Event::new(section, name, kind)
+    }
+
+    /// A package changed in the lock file.
+    pub fn locked(change: &LockChange) -> Self {
+        let kind = EventKind::Locked {
+            old: change.old.clone(),
+            new: change.new.clone(),
+        };
+        Event::new(&[], &change.name, kind)
+    }
+
+    /// An override for a dependency was added to `section`, either `["patch", <source>]` or
+    /// `["replace"]`.
+    pub fn patched(section: &[String], name: &str) -> Self {
+        Event::new(section, name, EventKind::Patched)
+    }
+
+    /// The version of a package was changed from `old` to `new`.
+    pub fn version_changed(name: &str, old: &semver::Version, new: &semver::Version) -> Self {
+        let kind = EventKind::VersionChanged {
+            old: old.clone(),
+            new: new.clone(),
+        };
+        Event::new(&[], name, kind)
+    }
+
+    /// The requirement on a path dependency was updated from `old` to `new`.
+    pub fn updated(section: &[String], name: &str, old: &str, new: &str) -> Self {
+        let kind = EventKind::Updated {
+            old: old.to_string(),
+            new: new.to_string(),
+        };
+        Event::new(section, name, kind)
+    }
+
+    /// A dependency was hoisted with `requirement`, from the members declaring it with the given
+    /// requirements.
+    pub fn hoisted(name: &str, requirement: &str, members: &[(String, String)]) -> Self {
+        let kind = EventKind::Hoisted {
+            requirement: requirement.to_string(),
+            members: members.iter().map(|m| m.0.clone()).collect(),
+            requirements: members.iter().map(|m| m.1.clone()).collect(),
+        };
+        Event::new(&[], name, kind)
+    }
+
+    /// The command `operation` was undone.
+    pub fn undone(operation: &str) -> Self {
+        Event {
+            manifest_path: None,
+            section: Vec::new(),
+            name: None,
+            kind: EventKind::Undone {
+                operation: operation.to_string(),
+            },
+        }
+    }
+
+    /// The manifest at `path` was restored.
+    pub fn restored(path: &Path) -> Self {
+        Event {
+            manifest_path: Some(path.to_path_buf()),
+            section: Vec::new(),
+            name: None,
+            kind: EventKind::Restored,
+        }
+    }
+
+    /// The command failed with `message`, because of `causes`.
+    pub fn error(message: &str, causes: Vec<String>) -> Self {
+        Event {
+            manifest_path: None,
+            section: Vec::new(),
+            name: None,
+            kind: EventKind::Error {
+                message: message.to_string(),
+                causes,
+            },
+        }
+    }
+
+    /// Set the manifest the event is about.
+    pub fn in_manifest(mut self, manifest_path: &Path) -> Self {
+        self.manifest_path = Some(manifest_path.to_path_buf());
+        self
+    }
+
+    /// Print the event for humans. Errors go to stderr, everything else to stdout.
+    pub fn print_human(&self) -> Result<()> {
+        let name = self.name.as_deref().unwrap_or_default();
+        let section = self.section.join(".");
+        let (label, color, message) = match self.kind {
+            EventKind::Added {
+                ref version,
+                optional,
+                inherited,
+            } => {
+                let version = match *version {
+                    Some(ref version) => format!("v{}", version),
+                    None => "(unknown version)".to_string(),
+                };
+                let section = if self.section.len() == 1 {
+                    section
+                } else {
+                    format!("{} for target `{}`", self.section[2], self.section[1])
+                };
+                let message = format!(
+                    "{} {} to {}{}{}",
+                    name,
+                    version,
+                    if optional { "optional " } else { "" },
+                    section,
+                    if inherited {
+                        " (inherited from the workspace)"
+                    } else {
+                        ""
+                    }
+                );
+                ("Adding", Color::Green, message)
+            }
+            EventKind::Removed => (
+                "Removing",
+                Color::Green,
+                format!("{} from {}", name, section),
+            ),
+            EventKind::Upgraded { ref old, ref new } => (
+                "Upgrading",
+                Color::Green,
+                format!("{} v{} -> v{}", name, old, new),
+            ),
+            EventKind::Outdated {
+                ref old,
+                ref new,
+                change,
+            } => {
+                let incompatible = if change == RequirementChange::Breaking {
+                    " (incompatible)"
+                } else {
+                    ""
+                };
+                let message = format!("{} {} -> {} in {}{}", name, old, new, section, incompatible);
+                ("Outdated", Color::Yellow, message)
+            }
+            EventKind::Unused => ("Unused", Color::Yellow, format!("{} in {}", name, section)),
+            EventKind::Skipped { ref reason } if self.section.is_empty() => (
+                "Skipping",
+                Color::Yellow,
+                format!("{}: {}", name, reason),
+            ),
+            EventKind::Skipped { ref reason } => (
+                "Skipping",
+                Color::Yellow,
+                format!("{} in {}: {}", name, section, reason),
+            ),
+            EventKind::Patched if self.section[0] == "replace" => {
+                ("Replacing", Color::Green, name.to_string())
+            }
+            EventKind::Patched => (
+                "Patching",
+                Color::Green,
+                format!("{} from {}", name, self.section[1..].join(".")),
+            ),
+            EventKind::VersionChanged { ref old, ref new } => (
+                "Setting",
+                Color::Green,
+                format!("{} v{} -> v{}", name, old, new),
+            ),
+            EventKind::Updated { ref new, .. } => (
+                "Updating",
+                Color::Green,
+                format!("{} in {} to {}", name, section, new),
+            ),
+            EventKind::Hoisted {
+                ref requirement,
+                ref members,
+                ref requirements,
+            } => {
+                let members = members
+                    .iter()
+                    .zip(requirements)
+                    .map(|(member, requirement)| format!("{} ({})", member, requirement))
+                    .collect::<Vec<_>>();
+                let message = format!("{} {} from {}", name, requirement, members.join(", "));
+                ("Hoisting", Color::Green, message)
+            }
+            EventKind::Undone { ref operation } => {
+                ("Undone", Color::Green, format!("`{}`", operation))
+            }
+            EventKind::Restored => {
+                let path = self.manifest_path.as_ref().map(|path| path.display().to_string());
+                ("Restored", Color::Green, path.unwrap_or_default())
+            }
+            EventKind::Locked { ref old, ref new } => {
+                let change = LockChange {
+                    name: name.to_string(),
+                    old: old.clone(),
+                    new: new.clone(),
+                };
+                (
+                    change.action(),
+                    Color::Green,
+                    format!("{} in Cargo.lock", change),
+                )
+            }
+            EventKind::Error {
+                ref message,
+                ref causes,
+            } => {
+                eprintln!("Command failed due to unhandled error: {}\n", message);
+                for cause in causes {
+                    eprintln!("Caused by: {}", cause);
+                }
+                return Ok(());
+            }
+        };
+
+        let colorchoice = if atty::is(atty::Stream::Stdout) {
+            ColorChoice::Auto
+        } else {
+            ColorChoice::Never
+        };
+        let mut output = StandardStream::stdout(colorchoice);
+        output
+            .set_color(ColorSpec::new().set_fg(Some(color)).set_bold(true))
+            .chain_err(|| "Failed to set output colour")?;
+        write!(output, "{:>12}", label).chain_err(|| "Failed to write message")?;
+        output.reset().chain_err(|| "Failed to clear output colour")?;
+        writeln!(output, " {}", message).chain_err(|| "Failed to write message")?;
+        Ok(())
+    }
+}
+
+/// Print an event in the given format.
+pub fn emit(event: &Event, format: MessageFormat) -> Result<()> {
+    match format {
+        MessageFormat::Human => event.print_human(),
+        MessageFormat::Json => {
+            let json = serde_json::to_string(event).chain_err(|| "Failed to serialize event")?;
+            println!("{}", json);
+            Ok(())
+        }
+    }
+}
+
+/// Print the name of the package whose changes follow. This is only needed for humans, as JSON
+/// events contain the path of their manifest.
+pub fn print_member(member: &str, format: MessageFormat) {
+    if format == MessageFormat::Human {
+        println!("{}:", member);
+    }
+}
+
+/// Tell the user that nothing will be written, before printing the events of a dry run.
+pub fn print_dry_run() -> Result<()> {
+    let colorchoice = if atty::is(atty::Stream::Stdout) {
+        ColorChoice::Auto
+    } else {
+        ColorChoice::Never
+    };
+    let mut output = StandardStream::stdout(colorchoice);
+    output
+        .set_color(ColorSpec::new().set_fg(Some(Color::Cyan)).set_bold(true))
+        .chain_err(|| "Failed to set output colour")?;
+    write!(output, "Starting dry run. ").chain_err(|| "Failed to write dry run message")?;
+    output.reset().chain_err(|| "Failed to clear output colour")?;
+    writeln!(output, "Changes will not be saved.")
+        .chain_err(|| "Failed to write dry run message")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn json() {
+        let dep = Dependency::new("docopt").set_version("0.8");
+        let event = Event::added(&["dependencies".to_string()], &dep, false, false)
+            .in_manifest(Path::new("Cargo.toml"));
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            concat!(
+                r#"{"manifest_path":"Cargo.toml","section":["dependencies"],"crate":"docopt","#,
+                r#""event":"added","version":"0.8","optional":false,"inherited":false}"#
+            )
+        );
+
+        let event = Event::upgraded(&["dev-dependencies".to_string()], "regex", "0.2", "1.0");
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            concat!(
+                r#"{"section":["dev-dependencies"],"crate":"regex","#,
+                r#""event":"upgraded","old":"0.2","new":"1.0"}"#
+            )
+        );
+
+        let event = Event::error("Failed", vec!["Because".to_string()]);
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"error","message":"Failed","causes":["Because"]}"#
+        );
+    }
+}
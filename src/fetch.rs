@@ -58,6 +58,36 @@ pub fn get_latest_dependency(crate_name: &str, flag_allow_prerelease: bool) -> R
     Ok(dep)
 }
 
+/// Query all versions of a crate from crates.io that have not been yanked, latest first.
+/// Prereleases are left out unless `flag_allow_prerelease` is set.
+///
+/// This fails for the same reasons as `get_latest_dependency`.
+pub fn get_available_versions(
+    crate_name: &str,
+    flag_allow_prerelease: bool,
+) -> Result<Vec<semver::Version>> {
+    let versions = if env::var("CARGO_IS_TEST").is_ok() {
+        // A made-up history, so that tests can pick versions from it.
+        ["1.1.0-alpha.1", "1.0.0", "0.9.1", "0.8.3", "0.8.0", "0.1.3"]
+            .iter()
+            .map(|version| CrateVersion {
+                name: crate_name.to_string(),
+                version: semver::Version::parse(version).expect("valid test versions"),
+                yanked: false,
+            })
+            .collect()
+    } else {
+        fetch_cratesio(crate_name)?.versions
+    };
+
+    Ok(versions
+        .into_iter()
+        .filter(|v| flag_allow_prerelease || version_is_stable(v))
+        .filter(|v| !v.yanked)
+        .map(|v| v.version)
+        .collect())
+}
+
 // Checks whether a version object is a stable release
 fn version_is_stable(version: &CrateVersion) -> bool {
     !version.version.is_prerelease()
@@ -280,10 +310,8 @@ pub fn get_crate_name_from_path(path: &str) -> Result<String> {
 
 fn get_name_from_manifest(manifest: &Manifest) -> Result<String> {
     manifest
-        .data
-        .as_table()
-        .get("package")
-        .and_then(|m| m["name"].as_str().map(|s| s.to_string()))
+        .package_name()
+        .map(|name| name.to_string())
         .ok_or_else(|| ErrorKind::ParseCargoToml.into())
 }
 
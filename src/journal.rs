@@ -0,0 +1,250 @@
+//! Record the manifests each command writes, so that the last command can be undone.
+//!
+//! The journal lives in `target/cargo-edit/journal/` of the workspace. Each command that writes
+//! manifests adds one entry, holding the contents of the manifests before and after.
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use cargo_metadata;
+use serde_json;
+
+use errors::*;
+use manifest::{lock_exclusive, write_file};
+
+/// How many commands are kept in the journal. Older ones can not be undone.
+const MAX_ENTRIES: usize = 20;
+
+/// A manifest written by a command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournaledFile {
+    /// Path to the manifest
+    pub path: PathBuf,
+    /// Contents of the manifest before the command
+    pub original: String,
+    /// Contents the command wrote
+    pub written: String,
+}
+
+/// The manifests written by one command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// The command line, e.g. `cargo upgrade --all`
+    pub operation: String,
+    /// The manifests written
+    pub files: Vec<JournaledFile>,
+}
+
+impl JournalEntry {
+    /// An entry for the files written by the running command.
+    pub fn new(files: Vec<JournaledFile>) -> Self {
+        // Cargo runs subcommands as `cargo-<name> <name> <args>...`.
+        let args = env::args().skip(1).collect::<Vec<_>>();
+        JournalEntry {
+            operation: format!("cargo {}", args.join(" ")),
+            files,
+        }
+    }
+}
+
+/// The journal of a workspace.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    dir: PathBuf,
+}
+
+impl Journal {
+    /// The journal of the workspace `manifest_path` belongs to, in its target directory.
+    pub fn for_manifest(manifest_path: &Path) -> Result<Self> {
+        // The dependencies are not needed to find the target directory.
+        let metadata = cargo_metadata::metadata_deps(Some(manifest_path), false)
+            .chain_err(|| "Failed to find the target directory for the journal")?;
+        Ok(Journal {
+            dir: PathBuf::from(metadata.target_directory)
+                .join("cargo-edit")
+                .join("journal"),
+        })
+    }
+
+    /// The numbers of the entries, oldest first.
+    fn entries(&self) -> Result<Vec<u64>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut entries = fs::read_dir(&self.dir)
+            .chain_err(|| "Failed to read the journal")?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                name.trim_end_matches(".json").parse().ok()
+            })
+            .collect::<Vec<u64>>();
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn entry_path(&self, number: u64) -> PathBuf {
+        self.dir.join(format!("{:06}.json", number))
+    }
+
+    /// Add an entry. Only the most recent entries are kept.
+    pub fn record(&self, entry: &JournalEntry) -> Result<()> {
+        fs::create_dir_all(&self.dir).chain_err(|| "Failed to create the journal")?;
+        let entries = self.entries()?;
+        let number = entries.last().map(|last| last + 1).unwrap_or(1);
+        let contents = serde_json::to_string(entry).chain_err(|| "Failed to serialize entry")?;
+        fs::write(self.entry_path(number), contents)
+            .chain_err(|| "Failed to write to the journal")?;
+
+        let outdated = (entries.len() + 1).saturating_sub(MAX_ENTRIES);
+        for &old in &entries[..outdated] {
+            fs::remove_file(self.entry_path(old))
+                .chain_err(|| "Failed to remove old journal entry")?;
+        }
+        Ok(())
+    }
+
+    /// The most recent entry, if any.
+    pub fn last(&self) -> Result<Option<JournalEntry>> {
+        match self.entries()?.last() {
+            Some(&number) => {
+                let contents = fs::read_to_string(self.entry_path(number))
+                    .chain_err(|| "Failed to read the journal")?;
+                serde_json::from_str(&contents)
+                    .map(Some)
+                    .chain_err(|| "Invalid journal entry")
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Remove the most recent entry, e.g. after its changes have been reverted.
+    pub fn forget_last(&self) -> Result<()> {
+        if let Some(&number) = self.entries()?.last() {
+            fs::remove_file(self.entry_path(number))
+                .chain_err(|| "Failed to remove journal entry")?;
+        }
+        Ok(())
+    }
+
+    /// Restore the manifests written by the most recent command, and remove its entry. Nothing is
+    /// restored if any of them has changed since.
+    ///
+    /// All the manifests are locked, like `ChangeSet::apply` does, before any is checked.
+    pub fn undo(&self) -> Result<JournalEntry> {
+        let entry = self.last()?.ok_or(ErrorKind::NothingToUndo)?;
+
+        let mut files = Vec::new();
+        let mut changed = Vec::new();
+        for file in &entry.files {
+            match lock_unchanged(file)? {
+                Some(locked) => files.push(locked),
+                None => changed.push(file.path.display().to_string()),
+            }
+        }
+        if !changed.is_empty() {
+            return Err(ErrorKind::ChangedSinceEdit(entry.operation, changed).into());
+        }
+
+        for (file, locked) in entry.files.iter().zip(&mut files) {
+            write_file(locked, &file.original)
+                .chain_err(|| format!("Failed to restore {}", file.path.display()))?;
+        }
+        self.forget_last()?;
+        Ok(entry)
+    }
+}
+
+/// Open and lock a journaled manifest, if it still has the contents that were written. Returns
+/// `None` if it has been changed or removed since.
+fn lock_unchanged(file: &JournaledFile) -> Result<Option<File>> {
+    let mut locked = match OpenOptions::new().read(true).write(true).open(&file.path) {
+        Ok(locked) => locked,
+        Err(_) => return Ok(None),
+    };
+    lock_exclusive(&locked, &file.path)?;
+
+    let mut current = String::new();
+    if locked.read_to_string(&mut current).is_err() || current != file.written {
+        return Ok(None);
+    }
+    locked
+        .seek(SeekFrom::Start(0))
+        .chain_err(|| "Failed to rewind Cargo.toml")?;
+    Ok(Some(locked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal(dir: &Path) -> Journal {
+        Journal {
+            dir: dir.join("journal"),
+        }
+    }
+
+    fn entry(path: &Path, original: &str, written: &str) -> JournalEntry {
+        JournalEntry {
+            operation: "cargo add foo".to_string(),
+            files: vec![JournaledFile {
+                path: path.to_path_buf(),
+                original: original.to_string(),
+                written: written.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn undo_restores_last_entry() {
+        let dir = ::tempdir::TempDir::new("cargo-edit-journal").unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        let journal = journal(dir.path());
+
+        journal.record(&entry(&manifest, "a", "b")).unwrap();
+        journal.record(&entry(&manifest, "b", "c")).unwrap();
+        fs::write(&manifest, "c").unwrap();
+
+        journal.undo().unwrap();
+        assert_eq!(fs::read_to_string(&manifest).unwrap(), "b");
+        journal.undo().unwrap();
+        assert_eq!(fs::read_to_string(&manifest).unwrap(), "a");
+        assert!(journal.undo().is_err());
+    }
+
+    #[test]
+    fn undo_refuses_changed_files() {
+        let dir = ::tempdir::TempDir::new("cargo-edit-journal").unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        let journal = journal(dir.path());
+
+        journal.record(&entry(&manifest, "a", "b")).unwrap();
+        fs::write(&manifest, "changed").unwrap();
+
+        assert!(journal.undo().is_err());
+        assert_eq!(fs::read_to_string(&manifest).unwrap(), "changed");
+        assert!(journal.last().unwrap().is_some());
+    }
+
+    #[test]
+    fn requires_a_workspace() {
+        let dir = ::tempdir::TempDir::new("cargo-edit-journal").unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        fs::write(&manifest, "[package]\nname = ").unwrap();
+
+        assert!(Journal::for_manifest(&manifest).is_err());
+    }
+
+    #[test]
+    fn keeps_recent_entries() {
+        let dir = ::tempdir::TempDir::new("cargo-edit-journal").unwrap();
+        let journal = journal(dir.path());
+        for _ in 0..MAX_ENTRIES + 5 {
+            journal.record(&entry(dir.path(), "a", "b")).unwrap();
+        }
+        let entries = journal.entries().unwrap();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries.last(), Some(&(MAX_ENTRIES as u64 + 5)));
+    }
+}
@@ -6,10 +6,12 @@
     unused_qualifications
 )]
 
+extern crate atty;
 extern crate cargo_metadata;
 extern crate env_proxy;
 #[macro_use]
 extern crate error_chain;
+extern crate fs2;
 extern crate regex;
 extern crate reqwest;
 extern crate semver;
@@ -17,18 +19,41 @@ extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
+#[cfg(test)]
+extern crate tempdir;
 extern crate termcolor;
 extern crate toml_edit;
 
 mod crate_name;
 mod dependency;
+mod diagnostic;
+mod diff;
 mod errors;
+mod event;
 mod fetch;
+mod journal;
+mod lint;
+mod list;
+mod lockfile;
 mod manifest;
+mod requirement;
+mod unused;
 
 pub use crate_name::CrateName;
-pub use dependency::Dependency;
+pub use dependency::{Dependency, GitReference};
+pub use diagnostic::Diagnostic;
+pub use diff::unified_diff;
 pub use errors::*;
+pub use event::{emit, print_dry_run, print_member, Event, EventKind, MessageFormat};
 pub use fetch::{get_crate_name_from_github, get_crate_name_from_gitlab, get_crate_name_from_path,
-                get_latest_dependency};
-pub use manifest::{find, LocalManifest, Manifest};
+                get_available_versions, get_latest_dependency};
+pub use journal::{Journal, JournalEntry, JournaledFile};
+pub use lint::{lint, Lint, LintKind};
+pub use list::{list_dependencies, ListedDependency, SourceKind};
+pub use lockfile::{lock_changes, parse_lockfile, read_lockfile, update_lockfile, LockChange,
+                   LockedPackage};
+pub use manifest::{find, find_package, relative_path, workspace_members, ChangeSet, LocalManifest,
+                   Manifest};
+pub use requirement::{highest_matching_version, registry_requirement, requirement_at_version,
+                      requirement_change, unify_requirements, RequirementChange, Unification};
+pub use unused::{find_unused, is_referenced, unused_in, UnusedDependency};
@@ -0,0 +1,319 @@
+//! Checks for common mistakes in dependency declarations.
+use semver;
+use toml_edit;
+
+use manifest::Manifest;
+
+/// The kinds of problems `lint` looks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintKind {
+    /// A dependency with a wildcard (`*`) version requirement
+    WildcardRequirement,
+    /// A crate required in several sections, with requirements that differ
+    ConflictingRequirements,
+    /// A path dependency without a version in a crate that can be published
+    PathWithoutVersion,
+    /// A git dependency that is not pinned to a revision
+    UnpinnedGitDependency,
+    /// A dev-dependency marked as optional
+    OptionalDevDependency,
+}
+
+/// A problem with a dependency declaration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Lint {
+    /// What kind of problem this is
+    pub kind: LintKind,
+    /// Path to the section containing the dependency, e.g. `["target", "cfg(unix)",
+    /// "dependencies"]`
+    pub section: Vec<String>,
+    /// The name of the dependency, as it is written in the manifest
+    #[serde(rename = "crate")]
+    pub name: String,
+    /// Human readable description of the problem
+    pub message: String,
+}
+
+/// The parts of a dependency declaration the lints look at.
+struct DependencySpec<'a> {
+    /// The name of the crate on its registry, taking renames into account
+    package: &'a str,
+    version: Option<&'a str>,
+    path: bool,
+    git: bool,
+    rev: bool,
+    optional: bool,
+}
+
+impl<'a> DependencySpec<'a> {
+    fn from_toml(name: &'a str, item: &'a toml_edit::Item) -> Self {
+        if let Some(version) = item.as_str() {
+            return DependencySpec {
+                package: name,
+                version: Some(version),
+                path: false,
+                git: false,
+                rev: false,
+                optional: false,
+            };
+        }
+
+        DependencySpec {
+            package: item["package"].as_str().unwrap_or(name),
+            version: item["version"].as_str(),
+            path: !item["path"].is_none(),
+            git: !item["git"].is_none(),
+            rev: !item["rev"].is_none(),
+            optional: item["optional"].as_bool().unwrap_or(false),
+        }
+    }
+}
+
+/// A version requirement for a crate, and where it was found.
+struct Requirement<'a> {
+    section: &'a Vec<String>,
+    name: &'a str,
+    version: &'a str,
+}
+
+/// Whether two version requirements mean the same thing, e.g. `1.0` and `^1.0`.
+fn same_requirement(a: &str, b: &str) -> bool {
+    match (semver::VersionReq::parse(a), semver::VersionReq::parse(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.trim() == b.trim(),
+    }
+}
+
+/// Whether the manifest describes a crate that can be published, i.e. `publish` is neither
+/// `false` nor an empty list of registries.
+fn is_publishable(manifest: &Manifest) -> bool {
+    let package = if manifest.data["package"].is_none() {
+        &manifest.data["project"]
+    } else {
+        &manifest.data["package"]
+    };
+
+    let publish = &package["publish"];
+    if let Some(publish) = publish.as_bool() {
+        publish
+    } else if let Some(registries) = publish.as_array() {
+        !registries.is_empty()
+    } else {
+        true
+    }
+}
+
+/// Check all dependency sections of a manifest for common mistakes.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate cargo_edit;
+/// # fn main() {
+///     use cargo_edit::{lint, LintKind, Manifest};
+///
+///     let manifest: Manifest = "[dependencies]\nserde = \"*\"".parse().unwrap();
+///     let lints = lint(&manifest);
+///     assert_eq!(lints.len(), 1);
+///     assert_eq!(lints[0].kind, LintKind::WildcardRequirement);
+/// # }
+/// ```
+pub fn lint(manifest: &Manifest) -> Vec<Lint> {
+    let publishable = is_publishable(manifest);
+    let sections = manifest.get_sections();
+    let mut lints = Vec::new();
+    // Every version requirement of each crate, in the order they were found.
+    let mut requirements: Vec<(&str, Vec<Requirement>)> = Vec::new();
+
+    for (section, table) in &sections {
+        let kind = section.last().expect("sections are never empty");
+        let is_dev = kind == "dev-dependencies";
+        let table = table.as_table_like().expect("Unexpected non-table");
+
+        for (name, item) in table.iter() {
+            let spec = DependencySpec::from_toml(name, item);
+            let mut report = |kind, message: String| {
+                lints.push(Lint {
+                    kind,
+                    section: section.clone(),
+                    name: name.to_string(),
+                    message,
+                })
+            };
+
+            if let Some(version) = spec.version {
+                if version.trim() == "*" {
+                    report(
+                        LintKind::WildcardRequirement,
+                        format!(
+                            "`{}` in `{}` has a wildcard version requirement",
+                            name,
+                            section.join(".")
+                        ),
+                    );
+                }
+
+                let requirement = Requirement {
+                    section,
+                    name,
+                    version,
+                };
+                match requirements
+                    .iter_mut()
+                    .find(|&&mut (package, _)| package == spec.package)
+                {
+                    Some(&mut (_, ref mut found)) => found.push(requirement),
+                    None => requirements.push((spec.package, vec![requirement])),
+                }
+            }
+
+            if spec.path && spec.version.is_none() && publishable && !is_dev {
+                report(
+                    LintKind::PathWithoutVersion,
+                    format!(
+                        "`{}` in `{}` is a path dependency without a version, so this crate \
+                         can not be published",
+                        name,
+                        section.join(".")
+                    ),
+                );
+            }
+
+            if spec.git && !spec.rev {
+                report(
+                    LintKind::UnpinnedGitDependency,
+                    format!(
+                        "`{}` in `{}` is a git dependency that is not pinned to a `rev`",
+                        name,
+                        section.join(".")
+                    ),
+                );
+            }
+
+            if spec.optional && is_dev {
+                report(
+                    LintKind::OptionalDevDependency,
+                    format!(
+                        "`{}` in `{}` is marked as optional, which dev-dependencies can not be",
+                        name,
+                        section.join(".")
+                    ),
+                );
+            }
+        }
+    }
+
+    for (package, found) in requirements {
+        let first = &found[0];
+        if found
+            .iter()
+            .all(|requirement| same_requirement(requirement.version, first.version))
+        {
+            continue;
+        }
+
+        let all = found
+            .iter()
+            .map(|r| format!("`{}` in `{}`", r.version, r.section.join(".")))
+            .collect::<Vec<_>>();
+        lints.push(Lint {
+            kind: LintKind::ConflictingRequirements,
+            section: first.section.clone(),
+            name: first.name.to_string(),
+            message: format!(
+                "`{}` has conflicting version requirements: {}",
+                package,
+                all.join(", ")
+            ),
+        });
+    }
+
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint_kinds(manifest: &str) -> Vec<LintKind> {
+        let manifest: Manifest = manifest.parse().unwrap();
+        lint(&manifest).into_iter().map(|l| l.kind).collect()
+    }
+
+    #[test]
+    fn clean_manifest() {
+        assert!(
+            lint_kinds(
+                r#"
+[package]
+name = "foo"
+version = "0.1.0"
+
+[dependencies]
+serde = "1.0"
+bar = { path = "../bar", version = "0.1" }
+baz = { git = "https://example.com/baz", rev = "abcdef" }
+
+[dev-dependencies]
+serde = "^1.0"
+"#
+            ).is_empty()
+        );
+    }
+
+    #[test]
+    fn conflicting_requirements() {
+        assert_eq!(
+            lint_kinds(
+                r#"
+[dependencies]
+serde = "1.0"
+
+[target.'cfg(unix)'.build-dependencies]
+serde_renamed = { package = "serde", version = "0.9" }
+"#
+            ),
+            vec![LintKind::ConflictingRequirements]
+        );
+    }
+
+    #[test]
+    fn path_without_version() {
+        let manifest = r#"
+[package]
+name = "foo"
+version = "0.1.0"
+
+[dependencies]
+bar = { path = "../bar" }
+
+[dev-dependencies]
+baz = { path = "../baz" }
+"#;
+        assert_eq!(lint_kinds(manifest), vec![LintKind::PathWithoutVersion]);
+        assert_eq!(
+            lint_kinds(&manifest.replace("[dependencies]", "[build-dependencies]")),
+            vec![LintKind::PathWithoutVersion]
+        );
+        assert!(
+            lint_kinds(&manifest.replace("version = \"0.1.0\"", "publish = false")).is_empty()
+        );
+    }
+
+    #[test]
+    fn unpinned_git_and_optional_dev_dependency() {
+        assert_eq!(
+            lint_kinds(
+                r#"
+[dev-dependencies]
+foo = { git = "https://example.com/foo", branch = "master", optional = true }
+"#
+            ),
+            vec![
+                LintKind::UnpinnedGitDependency,
+                LintKind::OptionalDevDependency,
+            ]
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Describe the dependencies declared in a manifest, for `cargo list`.
+use toml_edit;
+
+use manifest::Manifest;
+
+/// Where a dependency comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SourceKind {
+    /// A registry, i.e. crates.io
+    Registry,
+    /// A git repository
+    Git,
+    /// A local directory
+    Path,
+    /// Inherited from `[workspace.dependencies]` in the workspace root manifest
+    Workspace,
+}
+
+/// A dependency declared in a manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListedDependency {
+    /// Path to the section containing the dependency, e.g. `["target", "cfg(unix)",
+    /// "dependencies"]`
+    pub section: Vec<String>,
+    /// The name of the dependency, as it is written in the manifest
+    #[serde(rename = "crate")]
+    pub name: String,
+    /// The name of the crate on its registry, which differs from `name` if it is renamed
+    pub package: String,
+    /// The version requirement, if any
+    pub requirement: Option<String>,
+    /// Where the dependency comes from
+    pub source: SourceKind,
+    /// The git repository or path of the dependency, if it comes from one
+    pub location: Option<String>,
+    /// The features enabled in addition to the default ones
+    pub features: Vec<String>,
+    /// Whether the dependency is optional
+    pub optional: bool,
+}
+
+impl ListedDependency {
+    fn from_toml(section: &[String], name: &str, item: &toml_edit::Item) -> Self {
+        let string = |key: &str| item[key].as_str().map(|value| value.to_string());
+        let requirement = item.as_str().map(|value| value.to_string());
+        let (source, location) = if requirement.is_some() {
+            (SourceKind::Registry, None)
+        } else if item["workspace"].as_bool().unwrap_or(false) {
+            (SourceKind::Workspace, None)
+        } else if let Some(git) = string("git") {
+            (SourceKind::Git, Some(git))
+        } else if let Some(path) = string("path") {
+            (SourceKind::Path, Some(path))
+        } else {
+            (SourceKind::Registry, None)
+        };
+
+        ListedDependency {
+            section: section.to_vec(),
+            name: name.to_string(),
+            package: string("package").unwrap_or_else(|| name.to_string()),
+            requirement: requirement.or_else(|| string("version")),
+            source,
+            location,
+            features: item["features"]
+                .as_array()
+                .map(|features| {
+                    features
+                        .iter()
+                        .filter_map(|feature| feature.as_str())
+                        .map(|feature| feature.to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            optional: item["optional"].as_bool().unwrap_or(false),
+        }
+    }
+
+    /// Whether the dependency is looked up on a registry, so that a newer version can be found
+    /// there.
+    pub fn is_registry(&self) -> bool {
+        self.source == SourceKind::Registry && self.requirement.is_some()
+    }
+}
+
+/// List the dependencies in every section of a manifest that can contain them: normal
+/// dependencies first, then dev- and build-dependencies, each followed by their target-specific
+/// sections, and `[workspace.dependencies]` last.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate cargo_edit;
+/// # fn main() {
+///     use cargo_edit::{list_dependencies, Manifest, SourceKind};
+///
+///     let manifest: Manifest = "[dependencies]\nlocal = { path = \"../local\" }"
+///         .parse()
+///         .unwrap();
+///     let dependencies = list_dependencies(&manifest);
+///     assert_eq!(dependencies[0].source, SourceKind::Path);
+///     assert_eq!(dependencies[0].location, Some("../local".to_string()));
+/// # }
+/// ```
+pub fn list_dependencies(manifest: &Manifest) -> Vec<ListedDependency> {
+    let mut sections = manifest.get_sections();
+    sections.sort_by_key(|(section, _)| {
+        let kind = ["dependencies", "dev-dependencies", "build-dependencies"]
+            .iter()
+            .position(|kind| section.last().map(String::as_str) == Some(*kind));
+        (section[0] == "workspace", kind)
+    });
+
+    sections
+        .iter()
+        .flat_map(|(section, table)| {
+            table
+                .as_table_like()
+                .expect("Unexpected non-table")
+                .iter()
+                .map(|(name, item)| ListedDependency::from_toml(section, name, item))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_sections() {
+        let manifest: Manifest = r#"
+[package]
+name = "foo"
+
+[dependencies]
+docopt = "0.8"
+json = { version = "1.0", package = "serde_json", optional = true, features = ["std"] }
+clippy = { git = "https://github.com/rust-lang/rust-clippy" }
+libc = { workspace = true }
+
+[dev-dependencies]
+tempdir = "0.3"
+
+[target.'cfg(unix)'.build-dependencies]
+cc = "1.0"
+"#
+            .parse()
+            .unwrap();
+        let dependencies = list_dependencies(&manifest);
+        let names = dependencies
+            .iter()
+            .map(|dep| format!("{}:{}", dep.section.join("."), dep.name))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "dependencies:docopt",
+                "dependencies:json",
+                "dependencies:clippy",
+                "dependencies:libc",
+                "dev-dependencies:tempdir",
+                "target.cfg(unix).build-dependencies:cc",
+            ]
+        );
+
+        let json = &dependencies[1];
+        assert_eq!(json.package, "serde_json");
+        assert_eq!(json.requirement, Some("1.0".to_string()));
+        assert_eq!(json.features, vec!["std"]);
+        assert!(json.optional);
+        assert!(json.is_registry());
+        assert_eq!(dependencies[2].source, SourceKind::Git);
+        assert!(!dependencies[2].is_registry());
+        assert_eq!(dependencies[3].source, SourceKind::Workspace);
+    }
+}
@@ -0,0 +1,302 @@
+//! Read the versions recorded in a lock file (`Cargo.lock`).
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+use std::{env, fmt, fs};
+
+use cargo_metadata;
+use semver;
+use toml_edit;
+
+use errors::*;
+
+/// A package recorded in a lock file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    /// Name of the package
+    pub name: String,
+    /// The exact version the package is locked to
+    pub version: semver::Version,
+    /// Where the package comes from, e.g. `registry+https://github.com/rust-lang/crates.io-index`.
+    /// Workspace members and path dependencies have no source.
+    pub source: Option<String>,
+}
+
+impl LockedPackage {
+    /// Whether the package comes from a registry, rather than from git or a path.
+    pub fn is_registry(&self) -> bool {
+        self.source
+            .as_ref()
+            .map(|source| source.starts_with("registry+"))
+            .unwrap_or(false)
+    }
+}
+
+/// Read the packages recorded in the lock file at `path`.
+pub fn read_lockfile(path: &Path) -> Result<Vec<LockedPackage>> {
+    let contents = fs::read_to_string(path)
+        .chain_err(|| format!("Failed to read lock file {}", path.display()))?;
+    parse_lockfile(&contents)
+}
+
+/// Parse the contents of a lock file.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate cargo_edit;
+/// # fn main() {
+///     use cargo_edit::parse_lockfile;
+///
+///     let lockfile = r#"
+/// [[package]]
+/// name = "libc"
+/// version = "0.2.43"
+/// source = "registry+https://github.com/rust-lang/crates.io-index"
+/// "#;
+///     let packages = parse_lockfile(lockfile).unwrap();
+///     assert_eq!(packages[0].name, "libc");
+///     assert_eq!(packages[0].version.to_string(), "0.2.43");
+///     assert!(packages[0].is_registry());
+/// # }
+/// ```
+pub fn parse_lockfile(contents: &str) -> Result<Vec<LockedPackage>> {
+    let data: toml_edit::Document = contents.parse().chain_err(|| "Lock file not valid TOML")?;
+    let packages = match data.as_table().get("package") {
+        Some(packages) => packages
+            .as_array_of_tables()
+            .ok_or_else(|| Error::from("Lock file has no list of packages"))?,
+        None => return Ok(Vec::new()),
+    };
+
+    packages
+        .iter()
+        .map(|package| {
+            let field = |key| {
+                package
+                    .get(key)
+                    .and_then(|item| item.as_str())
+                    .map(|value| value.to_string())
+            };
+            let name = field("name").ok_or_else(|| Error::from("Locked package has no name"))?;
+            let version = field("version")
+                .ok_or_else(|| Error::from(format!("Locked package `{}` has no version", name)))?;
+            let version = semver::Version::parse(&version)
+                .chain_err(|| format!("Invalid version for locked package `{}`", name))?;
+            Ok(LockedPackage {
+                name,
+                version,
+                source: field("source"),
+            })
+        })
+        .collect()
+}
+
+/// A change to the packages recorded in a lock file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockChange {
+    /// Name of the package
+    pub name: String,
+    /// The version locked before, if any
+    pub old: Option<semver::Version>,
+    /// The version locked now, if any
+    pub new: Option<semver::Version>,
+}
+
+impl LockChange {
+    /// What happened to the package, in the words `cargo` uses, e.g. `Updating`.
+    pub fn action(&self) -> &'static str {
+        match (&self.old, &self.new) {
+            (Some(_), Some(_)) => "Updating",
+            (None, _) => "Adding",
+            (_, None) => "Removing",
+        }
+    }
+}
+
+impl fmt::Display for LockChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.old.as_ref(), self.new.as_ref()) {
+            (Some(old), Some(new)) => write!(f, "{} v{} -> v{}", self.name, old, new),
+            (Some(version), None) | (None, Some(version)) => {
+                write!(f, "{} v{}", self.name, version)
+            }
+            (None, None) => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Compare the packages of two lock files. A package whose only locked version changed is
+/// reported as updated, otherwise each version is reported as added or removed.
+pub fn lock_changes(old: &[LockedPackage], new: &[LockedPackage]) -> Vec<LockChange> {
+    let mut versions: BTreeMap<&str, (Vec<&semver::Version>, Vec<&semver::Version>)> =
+        BTreeMap::new();
+    for package in old {
+        versions.entry(&package.name).or_default().0.push(&package.version);
+    }
+    for package in new {
+        versions.entry(&package.name).or_default().1.push(&package.version);
+    }
+
+    let mut changes = Vec::new();
+    for (name, (old, new)) in versions {
+        let removed: Vec<_> = old.iter().filter(|v| !new.contains(v)).cloned().collect();
+        let added: Vec<_> = new.iter().filter(|v| !old.contains(v)).cloned().collect();
+        let change = |old: Option<&semver::Version>, new: Option<&semver::Version>| LockChange {
+            name: name.to_string(),
+            old: old.cloned(),
+            new: new.cloned(),
+        };
+        if removed.len() == 1 && added.len() == 1 {
+            changes.push(change(Some(removed[0]), Some(added[0])));
+        } else {
+            changes.extend(removed.iter().map(|&v| change(Some(v), None)));
+            changes.extend(added.iter().map(|&v| change(None, Some(v))));
+        }
+    }
+    changes
+}
+
+/// The version of the `cargo` binary, if it can be found out.
+fn cargo_version(cargo: &str) -> Option<semver::Version> {
+    let output = Command::new(cargo).arg("--version").output().ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    // E.g. `cargo 1.36.0 (c4fcfb725 2019-05-15)`.
+    let version = output.split_whitespace().nth(1)?;
+    semver::Version::parse(version).ok()
+}
+
+/// Bring the lock file of the workspace `manifest_path` belongs to in line with its manifests,
+/// like `cargo update -p <crate>` for each of `crates` would. New dependencies are resolved as
+/// well, without updating any others. Resolution is tried offline first if `cargo` supports it,
+/// and only goes online if that fails. Nothing is done if `crates` is empty.
+///
+/// Returns the changes to the lock file.
+pub fn update_lockfile(manifest_path: &Path, crates: &[String]) -> Result<Vec<LockChange>> {
+    if crates.is_empty() {
+        return Ok(Vec::new());
+    }
+    let metadata = cargo_metadata::metadata(Some(manifest_path))
+        .chain_err(|| "Failed to get workspace metadata")?;
+    let lockfile = Path::new(&metadata.workspace_root).join("Cargo.lock");
+    let old = if lockfile.exists() {
+        read_lockfile(&lockfile)?
+    } else {
+        Vec::new()
+    };
+
+    // Name each locked version, as `-p <crate>` is ambiguous if there are several. The
+    // `<crate>:<version>` form is understood by every version of `cargo`.
+    let packages = old
+        .iter()
+        .filter(|package| crates.contains(&package.name))
+        .map(|package| format!("{}:{}", package.name, package.version))
+        .collect::<Vec<_>>();
+    let mut args = if packages.is_empty() {
+        // None of the crates is locked yet. Resolving the workspace adds them, and keeps the
+        // locked versions of all the others, which `cargo update` without `-p` would not.
+        vec!["metadata".to_string(), "--format-version".to_string(), "1".to_string()]
+    } else {
+        let mut args = vec!["update".to_string()];
+        for package in packages {
+            args.push("-p".to_string());
+            args.push(package);
+        }
+        args
+    };
+    args.push("--manifest-path".to_string());
+    args.push(manifest_path.display().to_string());
+
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    // `--offline` is only known to cargo 1.36 and later.
+    let offline = cargo_version(&cargo)
+        .map(|version| (version.major, version.minor) >= (1, 36))
+        .unwrap_or(false);
+    let run = |offline: bool| {
+        let mut command = Command::new(&cargo);
+        command.args(&args);
+        if offline {
+            command.arg("--offline");
+        }
+        command
+            .output()
+            .chain_err(|| format!("Failed to run `{}`", cargo))
+    };
+    let mut output = run(offline)?;
+    if offline && !output.status.success() {
+        output = run(false)?;
+    }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(ErrorKind::LockfileUpdateFailed(stderr).into());
+    }
+
+    Ok(lock_changes(&old, &read_lockfile(&lockfile)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let lockfile = r#"
+[[package]]
+name = "one"
+version = "0.1.0"
+dependencies = [
+ "libc 0.2.43 (registry+https://github.com/rust-lang/crates.io-index)",
+]
+
+[[package]]
+name = "libc"
+version = "0.2.43"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[metadata]
+"#;
+        let packages = parse_lockfile(lockfile).unwrap();
+        assert_eq!(packages.len(), 2);
+        assert!(!packages[0].is_registry());
+        assert!(packages[1].is_registry());
+        assert_eq!(packages[1].version, semver::Version::parse("0.2.43").unwrap());
+    }
+
+    #[test]
+    fn changes() {
+        let package = |name: &str, version| LockedPackage {
+            name: name.to_string(),
+            version: semver::Version::parse(version).unwrap(),
+            source: None,
+        };
+        let old = vec![
+            package("libc", "0.2.28"),
+            package("rand", "0.4.0"),
+            package("rand", "0.5.0"),
+            package("regex", "1.0.0"),
+        ];
+        let new = vec![
+            package("libc", "0.2.40"),
+            package("rand", "0.5.0"),
+            package("regex", "1.0.0"),
+            package("serde", "1.0.80"),
+        ];
+        let changes = lock_changes(&old, &new)
+            .iter()
+            .map(|change| format!("{} {}", change.action(), change))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                "Updating libc v0.2.28 -> v0.2.40",
+                "Removing rand v0.4.0",
+                "Adding serde v1.0.80",
+            ]
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(parse_lockfile("version = 3\n").unwrap(), Vec::new());
+    }
+}
@@ -1,17 +1,69 @@
 use std::fs::{self, File, OpenOptions};
-use std::io::{Read, Write};
-use std::ops::Deref;
-use std::path::{Path, PathBuf};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::{Component, Path, PathBuf};
 use std::{env, str};
 
-use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, WriteColor};
+use cargo_metadata;
+use fs2::FileExt;
+use semver;
 use toml_edit;
 
 use dependency::Dependency;
+use diagnostic::{find_table_header, Diagnostic};
+use diff::unified_diff;
 use errors::*;
+use event::Event;
+use journal::{Journal, JournalEntry, JournaledFile};
+use lockfile::{update_lockfile, LockChange};
 
 const MANIFEST_FILENAME: &str = "Cargo.toml";
 
+/// The top-level keys a virtual manifest, i.e. a workspace root manifest without a `[package]`,
+/// can have.
+const VIRTUAL_MANIFEST_KEYS: &[&str] = &[
+    "workspace",
+    "patch",
+    "replace",
+    "profile",
+    "cargo-features",
+];
+
+/// Overwrite a manifest file with `contents`.
+pub fn write_file(file: &mut File, contents: &str) -> Result<()> {
+    let new_contents_bytes = contents.as_bytes();
+
+    // We need to truncate the file, otherwise the new contents
+    // will be mixed up with the old ones.
+    file.set_len(new_contents_bytes.len() as u64)
+        .chain_err(|| "Failed to truncate Cargo.toml")?;
+    file.write_all(new_contents_bytes)
+        .chain_err(|| "Failed to write updated Cargo.toml")
+}
+
+/// Take an exclusive advisory lock on a manifest, waiting for other processes holding it.
+pub fn lock_exclusive(file: &File, path: &Path) -> Result<()> {
+    // `File` has inherent locking methods in newer versions of Rust, which take precedence.
+    if FileExt::try_lock_exclusive(file).is_err() {
+        eprintln!("Blocking waiting for file lock on {}", path.display());
+        FileExt::lock_exclusive(file)
+            .chain_err(|| format!("Failed to lock {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Render a top-level item of a manifest, to compare it with another.
+fn render_item(item: &toml_edit::Item) -> String {
+    match *item {
+        toml_edit::Item::None => String::new(),
+        toml_edit::Item::Value(ref value) => value.to_string(),
+        toml_edit::Item::Table(ref table) => table.to_string(),
+        toml_edit::Item::ArrayOfTables(ref tables) => {
+            tables.iter().map(|table| table.to_string()).collect()
+        }
+    }
+}
+
 /// A Cargo manifest
 #[derive(Debug, Clone)]
 pub struct Manifest {
@@ -38,6 +90,72 @@ pub fn find(specified: &Option<PathBuf>) -> Result<PathBuf> {
     }
 }
 
+/// Get all members of the workspace that the manifest found by `find` belongs to.
+pub fn workspace_members(specified: &Option<PathBuf>) -> Result<Vec<cargo_metadata::Package>> {
+    let manifest_path = find(specified)?;
+    Ok(cargo_metadata::metadata(Some(&manifest_path))
+        .chain_err(|| "Failed to get workspace metadata")?
+        .packages)
+}
+
+/// Get the path to the manifest of the workspace member with the given name.
+pub fn find_package(specified: &Option<PathBuf>, name: &str) -> Result<PathBuf> {
+    workspace_members(specified)?
+        .into_iter()
+        .find(|package| package.name == name)
+        .map(|package| PathBuf::from(package.manifest_path))
+        .ok_or_else(|| ErrorKind::NonExistentPackage(name.into()).into())
+}
+
+/// Make a path absolute, and resolve `.` and `..` in it. The path does not have to exist.
+fn normalize(path: &Path) -> PathBuf {
+    if let Ok(path) = fs::canonicalize(path) {
+        return path;
+    }
+    let path = env::current_dir()
+        .map(|cwd| cwd.join(path))
+        .unwrap_or_else(|_| path.to_path_buf());
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// The path to `path` from the directory `dir`, e.g. to declare a path dependency on `path` in a
+/// manifest in `dir`. Neither has to exist.
+pub fn relative_path(dir: &Path, path: &Path) -> PathBuf {
+    let dir = normalize(dir);
+    let path = normalize(path);
+    let common = dir
+        .components()
+        .zip(path.components())
+        .take_while(|&(a, b)| a == b)
+        .count();
+    // Paths without a common root, e.g. on different drives, can only be given as they are.
+    if common == 0 {
+        return path;
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in dir.components().skip(common) {
+        relative.push("..");
+    }
+    for component in path.components().skip(common) {
+        relative.push(component);
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
 /// Search for Cargo.toml in this directory and recursively up the tree until one is found.
 fn search(dir: &Path) -> Result<PathBuf> {
     let manifest = dir.join(MANIFEST_FILENAME);
@@ -51,6 +169,72 @@ fn search(dir: &Path) -> Result<PathBuf> {
     }
 }
 
+/// How a manifest file is laid out, apart from its TOML contents. Manifests are parsed and rendered
+/// with `\n` line endings, and without a byte order mark. This is used to write them back the way
+/// they were read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileFormat {
+    /// Whether lines end with `\r\n`, as is common on Windows
+    crlf: bool,
+    /// Whether the file starts with a UTF-8 byte order mark
+    bom: bool,
+    /// Whether the last line ends with a newline
+    final_newline: bool,
+}
+
+impl FileFormat {
+    /// Detect the layout of `contents`. The line ending is the one of the first line.
+    fn detect(contents: &str) -> Self {
+        FileFormat {
+            crlf: contents
+                .find('\n')
+                .map(|index| contents[..index].ends_with('\r'))
+                .unwrap_or(false),
+            bom: contents.starts_with('\u{feff}'),
+            final_newline: contents.is_empty() || contents.ends_with('\n'),
+        }
+    }
+
+    /// Remove the byte order mark and `\r` line endings, for parsing.
+    fn normalize(contents: &str) -> String {
+        contents
+            .trim_start_matches('\u{feff}')
+            .replace("\r\n", "\n")
+    }
+
+    /// Lay out rendered TOML in this format.
+    fn apply(self, rendered: &str) -> String {
+        let mut contents = String::new();
+        if self.bom {
+            contents.push('\u{feff}');
+        }
+        let rendered = if self.final_newline || !rendered.ends_with('\n') {
+            rendered
+        } else {
+            &rendered[..rendered.len() - 1]
+        };
+        if self.crlf {
+            contents.push_str(&rendered.replace('\n', "\r\n"));
+        } else {
+            contents.push_str(rendered);
+        }
+        contents
+    }
+}
+
+/// Parse the contents of the manifest at `path`, which may have a byte order mark and `\r\n`
+/// line endings.
+fn parse_manifest(path: &Path, contents: &str) -> Result<Manifest> {
+    let contents = &FileFormat::normalize(contents);
+    match contents.parse() {
+        Ok(data) => Ok(Manifest { data }),
+        Err(err) => Err(match Diagnostic::from_toml_error(path, contents, &err) {
+            Some(diagnostic) => diagnostic.into(),
+            None => Error::with_chain(err, "Unable to parse Cargo.toml"),
+        }),
+    }
+}
+
 fn merge_inline_table(old_dep: &mut toml_edit::Item, new: &toml_edit::Item) {
     for (k, v) in new.as_inline_table()
         .expect("expected an inline table")
@@ -63,6 +247,11 @@ fn merge_inline_table(old_dep: &mut toml_edit::Item, new: &toml_edit::Item) {
 fn str_or_1_len_table(item: &toml_edit::Item) -> bool {
     item.is_str() || item.as_table_like().map(|t| t.len() == 1).unwrap_or(false)
 }
+
+/// Whether a dependency entry is inherited from the workspace, i.e. `foo = { workspace = true }`.
+fn is_inherited(item: &toml_edit::Item) -> bool {
+    item["workspace"].as_bool().unwrap_or(false)
+}
 /// Merge a new dependency into an old entry. See `Dependency::to_toml` for what the format of the
 /// new dependency will be.
 fn merge_dependencies(old_dep: &mut toml_edit::Item, new: &Dependency) {
@@ -74,7 +263,7 @@ fn merge_dependencies(old_dep: &mut toml_edit::Item, new: &Dependency) {
         // The old dependency is just a version/git/path. We are safe to overwrite.
         *old_dep = new_toml;
     } else if old_dep.is_table_like() {
-        for key in &["version", "path", "git"] {
+        for key in &["version", "path", "git", "branch", "tag", "rev", "workspace"] {
             // remove this key/value pairs
             old_dep[key] = toml_edit::Item::None;
         }
@@ -92,47 +281,26 @@ fn merge_dependencies(old_dep: &mut toml_edit::Item, new: &Dependency) {
     }
 }
 
-/// Print a message if the new dependency version is different from the old one.
-fn print_upgrade_if_necessary(
-    crate_name: &str,
+/// The old and new version requirement, if the new dependency changes the requirement of the old
+/// one.
+fn changed_version(
     old_dep: &toml_edit::Item,
-    new_version: &toml_edit::Item,
-) -> Result<()> {
+    new_dep: &toml_edit::Item,
+) -> Option<(String, String)> {
     let old_version = if str_or_1_len_table(old_dep) {
-        old_dep.clone()
+        old_dep.as_str()
     } else if old_dep.is_table_like() {
-        let version = old_dep["version"].clone();
-        if version.is_none() {
-            return Err("Missing version field".into());
-        }
-        version
+        old_dep["version"].as_str()
     } else {
         unreachable!("Invalid old dependency type")
     };
 
-    if let (Some(old_version), Some(new_version)) = (old_version.as_str(), new_version.as_str()) {
-        if old_version == new_version {
-            return Ok(());
+    match (old_version, new_dep.as_str()) {
+        (Some(old_version), Some(new_version)) if old_version != new_version => {
+            Some((old_version.to_string(), new_version.to_string()))
         }
-        let bufwtr = BufferWriter::stdout(ColorChoice::Always);
-        let mut buffer = bufwtr.buffer();
-        buffer
-            .set_color(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(true))
-            .chain_err(|| "Failed to set output colour")?;
-        write!(&mut buffer, "    Upgrading ").chain_err(|| "Failed to write upgrade message")?;
-        buffer
-            .set_color(&ColorSpec::new())
-            .chain_err(|| "Failed to clear output colour")?;
-        writeln!(
-            &mut buffer,
-            "{} v{} -> v{}",
-            crate_name, old_version, new_version,
-        ).chain_err(|| "Failed to write upgrade versions")?;
-        bufwtr
-            .print(&buffer)
-            .chain_err(|| "Failed to print upgrade message")?;
+        _ => None,
     }
-    Ok(())
 }
 
 impl Manifest {
@@ -152,12 +320,13 @@ impl Manifest {
 
     /// Open the `Cargo.toml` for a path (or the process' `cwd`)
     pub fn open(path: &Option<PathBuf>) -> Result<Manifest> {
-        let mut file = Manifest::find_file(path)?;
+        let path = find(path)?;
+        let mut file = Manifest::find_file(&Some(path.clone()))?;
         let mut data = String::new();
         file.read_to_string(&mut data)
             .chain_err(|| "Failed to read manifest contents")?;
 
-        data.parse().chain_err(|| "Unable to parse Cargo.toml")
+        parse_manifest(&path, &data)
     }
 
     /// Get the specified table from the manifest.
@@ -221,41 +390,146 @@ impl Manifest {
             sections.extend(target_sections);
         }
 
+        // Dependencies shared by the members of a workspace are defined in the root manifest.
+        if self.data["workspace"]["dependencies"].is_table_like() {
+            sections.push((
+                vec!["workspace".to_string(), "dependencies".to_string()],
+                self.data["workspace"]["dependencies"].clone(),
+            ))
+        }
+
         sections
     }
 
-    /// Overwrite a file with TOML data.
-    pub fn write_to_file(&self, file: &mut File) -> Result<()> {
-        if self.data["package"].is_none() && self.data["project"].is_none() {
-            if !self.data["workspace"].is_none() {
-                Err(ErrorKind::UnexpectedRootManifest)?;
-            } else {
-                Err(ErrorKind::InvalidManifest)?;
-            }
+    /// Get all sections in the manifest that override dependencies, i.e. `[patch.<source>]` and
+    /// `[replace]`. The returned items are always `Table` or `InlineTable`.
+    pub fn get_override_sections(&self) -> Vec<(Vec<String>, toml_edit::Item)> {
+        let mut sections: Vec<_> = self.data
+            .as_table()
+            .get("patch")
+            .and_then(toml_edit::Item::as_table_like)
+            .into_iter()
+            .flat_map(|t| t.iter())
+            .filter(|&(_, patch_table)| patch_table.is_table_like())
+            .map(|(source, patch_table)| {
+                (
+                    vec!["patch".to_string(), source.to_string()],
+                    patch_table.clone(),
+                )
+            })
+            .collect();
+
+        if self.data["replace"].is_table_like() {
+            sections.push((vec!["replace".to_string()], self.data["replace"].clone()));
+        }
+
+        sections
+    }
+
+    /// Check that an edit of the top-level `tables` (or keys) leaves a valid manifest.
+    ///
+    /// The manifest of a package can have any table. A virtual manifest, i.e. a workspace root
+    /// manifest without a `[package]`, can only have the tables that apply to the whole
+    /// workspace, such as `[workspace.dependencies]`, `[patch]` or `[profile]`.
+    pub fn check_edit<T: AsRef<str>>(&self, tables: &[T]) -> Result<()> {
+        if !self.data["package"].is_none() || !self.data["project"].is_none() {
+            return Ok(());
+        }
+        if self.data["workspace"].is_none() {
+            Err(ErrorKind::InvalidRootManifest)?;
         }
 
-        let s = self.data.to_string();
-        let new_contents_bytes = s.as_bytes();
+        match tables
+            .iter()
+            .map(AsRef::as_ref)
+            .find(|table| !VIRTUAL_MANIFEST_KEYS.contains(table))
+        {
+            Some(table) => Err(ErrorKind::VirtualManifestTable(table.to_string()).into()),
+            None => Ok(()),
+        }
+    }
 
-        // We need to truncate the file, otherwise the new contents
-        // will be mixed up with the old ones.
-        file.set_len(new_contents_bytes.len() as u64)
-            .chain_err(|| "Failed to truncate Cargo.toml")?;
-        file.write_all(new_contents_bytes)
-            .chain_err(|| "Failed to write updated Cargo.toml")
+    /// Check that the dependencies in `section` can be edited. Only the manifest of a package can
+    /// have dependency sections other than `[workspace.dependencies]`.
+    fn check_dependency_section(&self, section: &[String]) -> Result<()> {
+        if !self.data["package"].is_none()
+            || !self.data["project"].is_none()
+            || VIRTUAL_MANIFEST_KEYS.contains(&section[0].as_str())
+        {
+            Ok(())
+        } else if !self.data["workspace"].is_none() {
+            Err(ErrorKind::UnexpectedRootManifest.into())
+        } else {
+            Err(ErrorKind::InvalidManifest.into())
+        }
+    }
+
+    /// Overwrite a file with TOML data, keeping its line endings, byte order mark and final
+    /// newline.
+    ///
+    /// The whole manifest is checked with `check_edit`, so workspace root manifests without a
+    /// `[package]` are accepted as long as they only have tables that apply to the workspace.
+    #[deprecated(
+        since = "0.3.2",
+        note = "use `write_to_path`, which locks the manifest and records it in the journal"
+    )]
+    pub fn write_to_file(&self, file: &mut File) -> Result<()> {
+        let tables: Vec<&str> = self.data.as_table().iter().map(|(key, _)| key).collect();
+        self.check_edit(&tables)?;
+
+        let mut original = String::new();
+        file.read_to_string(&mut original)
+            .chain_err(|| "Failed to read manifest contents")?;
+        file.seek(SeekFrom::Start(0))
+            .chain_err(|| "Failed to rewind Cargo.toml")?;
+        let format = FileFormat::detect(&original);
+        write_file(file, &format.apply(&self.data.to_string()))
+    }
+
+    /// Overwrite the manifest at `path` with TOML data, and record it in the journal of the
+    /// workspace, so that `cargo edit undo` can restore the contents it replaced.
+    ///
+    /// The whole manifest is checked with `check_edit`, so workspace root manifests without a
+    /// `[package]` are accepted as long as they only have tables that apply to the workspace.
+    ///
+    /// The line endings, byte order mark and final newline of the file are kept.
+    ///
+    /// The file is locked while it is written. Changes made by other processes since the manifest
+    /// was read can not be detected, use `LocalManifest::write` for that.
+    pub fn write_to_path(&self, path: &Path) -> Result<()> {
+        let tables: Vec<&str> = self.data.as_table().iter().map(|(key, _)| key).collect();
+        self.check_edit(&tables)?;
+
+        let mut file = Manifest::find_file(&Some(path.to_path_buf()))?;
+        lock_exclusive(&file, path)?;
+        let mut original = String::new();
+        file.read_to_string(&mut original)
+            .chain_err(|| "Failed to read manifest contents")?;
+        file.seek(SeekFrom::Start(0))
+            .chain_err(|| "Failed to rewind Cargo.toml")?;
+
+        let journal = Journal::for_manifest(path)?;
+        let written = FileFormat::detect(&original).apply(&self.data.to_string());
+        let journaled = JournaledFile {
+            path: path.to_path_buf(),
+            original,
+            written,
+        };
+        write_file(&mut file, &journaled.written)?;
+        journal.record(&JournalEntry::new(vec![journaled]))
     }
 
     /// Add entry to a Cargo.toml.
     pub fn insert_into_table(&mut self, table_path: &[String], dep: &Dependency) -> Result<()> {
         let table = self.get_table(table_path)?;
 
-        if table[&dep.name].is_none() {
+        if table[dep.toml_key()].is_none() {
             // insert a new entry
             let (ref name, ref mut new_dependency) = dep.to_toml();
             table[name] = new_dependency.clone();
         } else {
             // update an existing entry
-            merge_dependencies(&mut table[&dep.name], dep);
+            merge_dependencies(&mut table[dep.toml_key()], dep);
             if let Some(t) = table.as_inline_table_mut() {
                 t.fmt()
             }
@@ -264,29 +538,37 @@ impl Manifest {
     }
 
     /// Update an entry in Cargo.toml.
+    ///
+    /// Returns what happened to the entry: an `upgraded` event if its version requirement changed,
+    /// or a `skipped` one if it is inherited from the workspace.
     pub fn update_table_entry(
         &mut self,
         table_path: &[String],
         dep: &Dependency,
-        dry_run: bool,
-    ) -> Result<()> {
+    ) -> Result<Option<Event>> {
         let table = self.get_table(table_path)?;
         let new_dep = dep.to_toml().1;
 
-        // If (and only if) there is an old entry, merge the new one in.
-        if !table[&dep.name].is_none() {
-            if let Err(e) = print_upgrade_if_necessary(&dep.name, &table[&dep.name], &new_dep) {
-                eprintln!("Error while displaying upgrade message, {}", e);
-            }
-            if !dry_run {
-                merge_dependencies(&mut table[&dep.name], dep);
-                if let Some(t) = table.as_inline_table_mut() {
-                    t.fmt()
-                }
-            }
+        // If (and only if) there is an old entry, merge the new one in. Entries inherited from the
+        // workspace are upgraded in `[workspace.dependencies]` instead.
+        if table[&dep.name].is_none() {
+            return Ok(None);
+        } else if is_inherited(&table[&dep.name]) {
+            return Ok(Some(Event::skipped(
+                table_path,
+                &dep.name,
+                "inherited from the workspace",
+            )));
         }
 
-        Ok(())
+        let event = changed_version(&table[&dep.name], &new_dep)
+            .map(|(old, new)| Event::upgraded(table_path, &dep.name, &old, &new));
+        merge_dependencies(&mut table[&dep.name], dep);
+        if let Some(t) = table.as_inline_table_mut() {
+            t.fmt()
+        }
+
+        Ok(event)
     }
 
     /// Remove entry from a Cargo.toml.
@@ -329,6 +611,112 @@ impl Manifest {
         Ok(())
     }
 
+    /// Remove a dependency from the section at `section`, e.g. `["target", "cfg(unix)",
+    /// "dependencies"]`. Tables left empty are removed as well.
+    pub fn remove_from_section(&mut self, section: &[String], name: &str) -> Result<()> {
+        fn descend<'a>(item: &'a mut toml_edit::Item, path: &[String]) -> &'a mut toml_edit::Item {
+            path.iter().fold(item, |item, key| &mut item[key.as_str()])
+        }
+
+        let table = section.join(".");
+        let found = section
+            .iter()
+            .fold(&self.data.root, |item, key| &item[key.as_str()]);
+        if !found.is_table_like() {
+            Err(ErrorKind::NonExistentTable(table))?;
+        } else if found[name].is_none() {
+            Err(ErrorKind::NonExistentDependency(name.into(), table))?;
+        }
+
+        descend(&mut self.data.root, section)[name] = toml_edit::Item::None;
+        for depth in (1..=section.len()).rev() {
+            let table = descend(&mut self.data.root, &section[..depth]);
+            if !table.as_table_like().map(|t| t.is_empty()).unwrap_or(false) {
+                break;
+            }
+            *table = toml_edit::Item::None;
+        }
+        Ok(())
+    }
+
+    /// Add a `[replace]` entry overriding a crate with the path or git repository of `dep`.
+    ///
+    /// Replacements are keyed by package ID specifications such as `foo:0.1.0`, made of the name
+    /// and version of `dep`, and do not take a version requirement themselves.
+    pub fn insert_replacement(&mut self, dep: &Dependency) -> Result<()> {
+        let key = match dep.version() {
+            Some(version) => format!("{}:{}", dep.name, version),
+            None => dep.name.clone(),
+        };
+        let mut entry = dep.to_toml().1;
+        if entry.is_table_like() {
+            entry["version"] = toml_edit::Item::None;
+        }
+        if let Some(t) = entry.as_inline_table_mut() {
+            t.fmt()
+        }
+
+        self.get_table(&["replace".to_string()])?[&key] = entry;
+        Ok(())
+    }
+
+    /// Remove a patch or replacement for a crate.
+    ///
+    /// The crate is removed from every `[patch.<source>]` table (or only from `[patch.<source>]`
+    /// if a source is given) and from `[replace]`. Tables left empty are removed as well. Returns
+    /// the paths of the sections the crate was removed from.
+    pub fn remove_override(
+        &mut self,
+        name: &str,
+        source: Option<&str>,
+    ) -> Result<Vec<Vec<String>>> {
+        let mut removed = Vec::new();
+
+        for (section, table) in self.get_override_sections() {
+            let keys: Vec<String> = table
+                .as_table_like()
+                .expect("Unexpected non-table")
+                .iter()
+                .map(|(key, _)| key.to_string())
+                .filter(|key| match section[0].as_str() {
+                    // `[replace]` entries are keyed by package ID specifications such as
+                    // `foo:0.1.0`.
+                    "replace" => source.is_none() && key.split(':').next() == Some(name),
+                    _ => key == name && source.map(|s| s == section[1]).unwrap_or(true),
+                })
+                .collect();
+            if keys.is_empty() {
+                continue;
+            }
+
+            let table = self.get_table(&section)?;
+            for key in keys {
+                table[&key] = toml_edit::Item::None;
+            }
+            if table.as_table_like().map(|t| t.is_empty()).unwrap_or(false) {
+                *table = toml_edit::Item::None;
+            }
+            removed.push(section);
+        }
+
+        if self.data["patch"]
+            .as_table_like()
+            .map(|t| t.is_empty())
+            .unwrap_or(false)
+        {
+            self.data["patch"] = toml_edit::Item::None;
+        }
+
+        if removed.is_empty() {
+            let table = source
+                .map(|s| format!("patch.{}", s))
+                .unwrap_or_else(|| "patch".to_string());
+            Err(ErrorKind::NonExistentDependency(name.into(), table))?;
+        }
+
+        Ok(removed)
+    }
+
     /// Add multiple dependencies to manifest
     pub fn add_deps(&mut self, table: &[String], deps: &[Dependency]) -> Result<()> {
         deps.iter()
@@ -337,6 +725,216 @@ impl Manifest {
 
         Ok(())
     }
+
+    /// Remove multiple dependencies from manifest
+    ///
+    /// Either all of the dependencies are removed, or (if any of them could not be found) the
+    /// manifest is left untouched and every missing dependency is reported.
+    ///
+    /// Returns the names of the dependencies removed, without duplicates.
+    pub fn remove_deps(&mut self, table: &str, names: &[String]) -> Result<Vec<String>> {
+        if !self.data[table].is_table_like() {
+            Err(ErrorKind::NonExistentTable(table.into()))?;
+        }
+
+        let names = names.iter().fold(Vec::new(), |mut unique, name| {
+            if !unique.contains(name) {
+                unique.push(name.clone());
+            }
+            unique
+        });
+
+        let mut missing: Vec<String> = names
+            .iter()
+            .filter(|name| self.data[table][name.as_str()].is_none())
+            .cloned()
+            .collect();
+        match missing.len() {
+            0 => {}
+            1 => Err(ErrorKind::NonExistentDependency(missing.remove(0), table.into()))?,
+            _ => Err(ErrorKind::NonExistentDependencies(missing, table.into()))?,
+        }
+
+        names
+            .iter()
+            .map(|name| self.remove_from_table(table, name))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(names)
+    }
+
+    /// The table holding the package metadata, `[package]` or its older alias `[project]`.
+    fn package_table_name(&self) -> &'static str {
+        if self.data["package"].is_none() && !self.data["project"].is_none() {
+            "project"
+        } else {
+            "package"
+        }
+    }
+
+    fn get_package_str(&self, key: &str) -> Option<&str> {
+        self.data[self.package_table_name()][key].as_str()
+    }
+
+    fn get_package_list(&self, key: &str) -> Option<Vec<&str>> {
+        self.data[self.package_table_name()][key]
+            .as_array()
+            .map(|array| array.iter().filter_map(|item| item.as_str()).collect())
+    }
+
+    /// Set `key` in the table at `table_path` to `raw`, a TOML value, keeping the whitespace and
+    /// comment around the value it replaces.
+    fn set_value(&mut self, table_path: &[&str], key: &str, raw: &str) -> Result<()> {
+        let (first, rest) = table_path.split_first().expect("Empty table path");
+        let table = rest
+            .iter()
+            .fold(&mut self.data[*first], |item, &name| &mut item[name]);
+        let (prefix, suffix) = match table[key].as_value() {
+            Some(old) => split_decor(&old.to_string()),
+            None => (" ".to_string(), String::new()),
+        };
+        let snippet: toml_edit::Document = format!("value ={}{}{}\n", prefix, raw, suffix)
+            .parse()
+            .chain_err(|| format!("Invalid value for `{}`", key))?;
+        table[key] = snippet["value"].clone();
+        Ok(())
+    }
+
+    /// Set a package field to `raw`, a TOML value, keeping the whitespace and comment around the
+    /// value it replaces.
+    fn set_package_value(&mut self, key: &str, raw: &str) -> Result<()> {
+        let table = self.package_table_name();
+        self.set_value(&[table], key, raw)
+    }
+
+    fn set_package_str(&mut self, key: &str, value: &str) -> Result<()> {
+        self.set_package_value(key, &toml_edit::Value::from(value).to_string())
+    }
+
+    fn set_package_list(&mut self, key: &str, values: &[&str]) -> Result<()> {
+        let items: Vec<String> = values
+            .iter()
+            .map(|&value| toml_edit::Value::from(value).to_string())
+            .collect();
+        self.set_package_value(key, &format!("[{}]", items.join(", ")))
+    }
+
+    /// The name of the package.
+    pub fn package_name(&self) -> Option<&str> {
+        self.get_package_str("name")
+    }
+
+    /// Set the name of the package.
+    pub fn set_package_name(&mut self, name: &str) -> Result<()> {
+        self.set_package_str("name", name)
+    }
+
+    /// The version of the package, if it is given as a string.
+    pub fn package_version(&self) -> Option<&str> {
+        self.get_package_str("version")
+    }
+
+    /// Set the version of the package.
+    pub fn set_package_version(&mut self, version: &semver::Version) -> Result<()> {
+        self.set_package_str("version", &version.to_string())
+    }
+
+    /// Set the version the members of the workspace inherit with `version.workspace = true`, i.e.
+    /// `[workspace.package].version`.
+    pub fn set_workspace_package_version(&mut self, version: &semver::Version) -> Result<()> {
+        let raw = toml_edit::Value::from(version.to_string()).to_string();
+        self.set_value(&["workspace", "package"], "version", &raw)
+    }
+
+    /// The Rust edition of the package.
+    pub fn package_edition(&self) -> Option<&str> {
+        self.get_package_str("edition")
+    }
+
+    /// Set the Rust edition of the package.
+    pub fn set_package_edition(&mut self, edition: &str) -> Result<()> {
+        self.set_package_str("edition", edition)
+    }
+
+    /// The minimum Rust version supported by the package.
+    pub fn package_rust_version(&self) -> Option<&str> {
+        self.get_package_str("rust-version")
+    }
+
+    /// Set the minimum Rust version supported by the package.
+    pub fn set_package_rust_version(&mut self, rust_version: &str) -> Result<()> {
+        self.set_package_str("rust-version", rust_version)
+    }
+
+    /// The authors of the package.
+    pub fn package_authors(&self) -> Option<Vec<&str>> {
+        self.get_package_list("authors")
+    }
+
+    /// Set the authors of the package.
+    pub fn set_package_authors(&mut self, authors: &[&str]) -> Result<()> {
+        self.set_package_list("authors", authors)
+    }
+
+    /// The license of the package, as an SPDX expression.
+    pub fn package_license(&self) -> Option<&str> {
+        self.get_package_str("license")
+    }
+
+    /// Set the license of the package.
+    pub fn set_package_license(&mut self, license: &str) -> Result<()> {
+        self.set_package_str("license", license)
+    }
+
+    /// The description of the package.
+    pub fn package_description(&self) -> Option<&str> {
+        self.get_package_str("description")
+    }
+
+    /// Set the description of the package.
+    pub fn set_package_description(&mut self, description: &str) -> Result<()> {
+        self.set_package_str("description", description)
+    }
+
+    /// The URL of the repository of the package.
+    pub fn package_repository(&self) -> Option<&str> {
+        self.get_package_str("repository")
+    }
+
+    /// Set the URL of the repository of the package.
+    pub fn set_package_repository(&mut self, repository: &str) -> Result<()> {
+        self.set_package_str("repository", repository)
+    }
+
+    /// The keywords of the package.
+    pub fn package_keywords(&self) -> Option<Vec<&str>> {
+        self.get_package_list("keywords")
+    }
+
+    /// Set the keywords of the package.
+    pub fn set_package_keywords(&mut self, keywords: &[&str]) -> Result<()> {
+        self.set_package_list("keywords", keywords)
+    }
+}
+
+/// Split a rendered value into the whitespace before it and the whitespace and comment after it.
+fn split_decor(rendered: &str) -> (String, String) {
+    let trimmed = rendered.trim_start();
+    let prefix = &rendered[..rendered.len() - trimmed.len()];
+    // A `#` may also appear inside the value, so take the first one after which the remainder
+    // parses as a value on its own.
+    let end = trimmed
+        .match_indices('#')
+        .map(|(index, _)| index)
+        .chain(Some(trimmed.len()))
+        .map(|index| trimmed[..index].trim_end().len())
+        .find(|&end| {
+            format!("value = {}\n", &trimmed[..end])
+                .parse::<toml_edit::Document>()
+                .is_ok()
+        })
+        .unwrap_or_else(|| trimmed.trim_end().len());
+    (prefix.to_string(), trimmed[end..].to_string())
 }
 
 impl str::FromStr for Manifest {
@@ -344,7 +942,9 @@ impl str::FromStr for Manifest {
 
     /// Read manifest data from string
     fn from_str(input: &str) -> ::std::result::Result<Self, Self::Err> {
-        let d: toml_edit::Document = input.parse().chain_err(|| "Manifest not valid TOML")?;
+        let d: toml_edit::Document = FileFormat::normalize(input)
+            .parse()
+            .chain_err(|| "Manifest not valid TOML")?;
 
         Ok(Manifest { data: d })
     }
@@ -357,6 +957,10 @@ pub struct LocalManifest {
     path: PathBuf,
     /// Manifest contents
     manifest: Manifest,
+    /// Contents of the file when it was read, to show what has changed since
+    original: String,
+    /// Line endings etc. of the file, to write it back the same way
+    format: FileFormat,
 }
 
 impl Deref for LocalManifest {
@@ -367,6 +971,12 @@ impl Deref for LocalManifest {
     }
 }
 
+impl DerefMut for LocalManifest {
+    fn deref_mut(&mut self) -> &mut Manifest {
+        &mut self.manifest
+    }
+}
+
 impl LocalManifest {
     /// Construct a `LocalManifest`. If no path is provided, make an educated guess as to which one
     /// the user means.
@@ -378,12 +988,184 @@ impl LocalManifest {
     /// Construct the `LocalManifest` corresponding to the `Path` provided.
     pub fn try_new(path: &Path) -> Result<Self> {
         let path = path.to_path_buf();
+        let mut original = String::new();
+        let mut file = Manifest::find_file(&Some(path.clone()))?;
+        // Do not read a manifest while another process is writing it.
+        FileExt::lock_shared(&file)
+            .chain_err(|| format!("Failed to lock {}", path.display()))?;
+        file.read_to_string(&mut original)
+            .chain_err(|| "Failed to read manifest contents")?;
         Ok(LocalManifest {
-            manifest: Manifest::open(&Some(path.clone()))?,
+            manifest: parse_manifest(&path, &original)?,
+            format: FileFormat::detect(&original),
+            original,
             path,
         })
     }
 
+    /// Check that this is the manifest of a package, and not a virtual manifest. `help` tells the
+    /// user how to select packages instead.
+    pub fn ensure_package(&self, help: &str) -> Result<()> {
+        if !self.data["package"].is_none() || !self.data["project"].is_none() {
+            return Ok(());
+        } else if self.data["workspace"].is_none() {
+            Err(ErrorKind::InvalidManifest)?;
+        }
+
+        Err(match find_table_header(&self.source(), "workspace") {
+            Some(line) => Diagnostic::new(
+                "Found virtual manifest, but this command requires running against an actual \
+                 package in this workspace.",
+                &self.path,
+                &self.source(),
+                line,
+                1,
+            ).set_help(help)
+                .into(),
+            None => ErrorKind::UnexpectedRootManifest.into(),
+        })
+    }
+
+    /// Point at the place in the manifest that an error returned by one of the `Manifest` methods
+    /// is about, if possible. Other errors are returned unchanged.
+    pub fn locate_error(&self, error: Error) -> Error {
+        let (names, table) = match *error.kind() {
+            ErrorKind::NonExistentDependency(ref name, ref table) => {
+                (vec![name.clone()], table.clone())
+            }
+            ErrorKind::NonExistentDependencies(ref names, ref table) => {
+                (names.clone(), table.clone())
+            }
+            _ => return error,
+        };
+        let line = match find_table_header(&self.source(), &table) {
+            Some(line) => line,
+            None => return error,
+        };
+
+        // Dependencies declared in another section may just have been looked for in the wrong one.
+        let elsewhere = names
+            .iter()
+            .filter_map(|name| {
+                self.get_sections()
+                    .into_iter()
+                    .find(|(_, other)| !other[name].is_none())
+                    .map(|(section, _)| {
+                        format!("`{}` is declared in `{}`", name, section.join("."))
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let diagnostic = Diagnostic::new(
+            &error.to_string(),
+            &self.path,
+            &self.source(),
+            line,
+            1,
+        );
+        if elsewhere.is_empty() {
+            diagnostic.into()
+        } else {
+            diagnostic.set_help(&elsewhere.join(", ")).into()
+        }
+    }
+
+    /// Path to the manifest.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The contents of the manifest when it was read, as they were parsed.
+    fn source(&self) -> String {
+        FileFormat::normalize(&self.original)
+    }
+
+    /// The contents to write to the file: the edited manifest, with the line endings, byte order
+    /// mark and final newline (or lack thereof) of the original file.
+    pub fn contents(&self) -> String {
+        self.format.apply(&self.data.to_string())
+    }
+
+    /// Whether the manifest has been edited since it was read.
+    pub fn is_modified(&self) -> bool {
+        self.original != self.contents()
+    }
+
+    /// Render the edits made since the manifest was read as a unified diff. Empty if there are
+    /// none.
+    pub fn diff(&self) -> String {
+        unified_diff(
+            &self.original,
+            &self.contents(),
+            &self.path.display().to_string(),
+        )
+    }
+
+    /// The top-level tables (or keys) that have been edited since the manifest was read.
+    pub fn edited_tables(&self) -> Vec<String> {
+        let original: toml_edit::Document = self.source().parse().unwrap_or_default();
+        let mut tables: Vec<String> = original
+            .as_table()
+            .iter()
+            .chain(self.data.as_table().iter())
+            .map(|(key, _)| key.to_string())
+            .filter(|key| render_item(&original[key]) != render_item(&self.data[key]))
+            .collect();
+        tables.sort();
+        tables.dedup();
+        tables
+    }
+
+    /// Write the manifest back to its file, and record it in the journal of the workspace.
+    ///
+    /// Only the tables that have been edited are checked with `Manifest::check_edit`. This allows
+    /// e.g. adding to `[workspace.dependencies]` in a virtual manifest, while adding to
+    /// `[dependencies]` there is an error.
+    pub fn write(&self) -> Result<()> {
+        self.check_edit(&self.edited_tables())?;
+        let mut file = self.lock()?;
+        let journal = Journal::for_manifest(&self.path)?;
+        write_file(&mut file, &self.contents())?;
+        journal.record(&JournalEntry::new(vec![self.journaled()]))
+    }
+
+    /// Open the manifest for writing, and take an exclusive advisory lock on it, like Cargo does
+    /// for the files it shares between processes. The lock is released when the file is closed.
+    ///
+    /// Fails if the manifest has been changed since it was read, e.g. by another `cargo add`
+    /// running at the same time, as writing it would lose those changes.
+    fn lock(&self) -> Result<File> {
+        let mut file = self.get_file()?;
+        lock_exclusive(&file, &self.path)?;
+
+        let mut current = String::new();
+        file.read_to_string(&mut current)
+            .chain_err(|| "Failed to read manifest contents")?;
+        if current != self.original {
+            return Err(ErrorKind::ManifestChanged(self.path.display().to_string()).into());
+        }
+        file.seek(SeekFrom::Start(0))
+            .chain_err(|| "Failed to rewind Cargo.toml")?;
+        Ok(file)
+    }
+
+    /// The journal record of writing this manifest.
+    fn journaled(&self) -> JournaledFile {
+        JournaledFile {
+            path: self.path.clone(),
+            original: self.original.clone(),
+            written: self.contents(),
+        }
+    }
+
+    /// Write the contents the manifest had when it was read back to its file, undoing any edits
+    /// written since.
+    pub fn restore(&self) -> Result<()> {
+        let mut file = self.get_file()?;
+        lock_exclusive(&file, &self.path)?;
+        write_file(&mut file, &self.original).chain_err(|| "Failed to restore Cargo.toml")
+    }
+
     /// Get the `File` corresponding to this manifest.
     fn get_file(&self) -> Result<File> {
         Manifest::find_file(&Some(self.path.clone()))
@@ -391,20 +1173,143 @@ impl LocalManifest {
 
     /// Instruct this manifest to upgrade a single dependency. If this manifest does not have that
     /// dependency, it does nothing.
-    pub fn upgrade(&mut self, dependency: &Dependency, dry_run: bool) -> Result<()> {
+    ///
+    /// Only the manifest in memory is changed. Add it to a `ChangeSet` to write it. Returns what
+    /// happened to the dependency in each section it was found in.
+    ///
+    /// In a virtual manifest, only `[workspace.dependencies]` can be upgraded.
+    pub fn upgrade(&mut self, dependency: &Dependency) -> Result<Vec<Event>> {
+        let mut events = Vec::new();
         for (table_path, table) in self.get_sections() {
             let table_like = table.as_table_like().expect("Unexpected non-table");
             for (name, _old_value) in table_like.iter() {
                 if name == dependency.name {
-                    self.manifest
-                        .update_table_entry(&table_path, dependency, dry_run)?;
+                    self.check_dependency_section(&table_path)?;
+                    if let Some(event) =
+                        self.manifest.update_table_entry(&table_path, dependency)?
+                    {
+                        events.push(event.in_manifest(&self.path));
+                    }
                 }
             }
         }
 
-        let mut file = self.get_file()?;
-        self.write_to_file(&mut file)
-            .chain_err(|| "Failed to write new manifest contents")
+        Ok(events)
+    }
+}
+
+/// Edited manifests waiting to be written.
+///
+/// Commands make all their edits in memory first, and collect the manifests here. Nothing is
+/// written to disk until `apply` is called, so a dry run simply never calls it.
+#[derive(Debug, Default)]
+pub struct ChangeSet {
+    manifests: Vec<LocalManifest>,
+    /// The journal `apply` recorded the changes in, if it has been called
+    journal: Option<Journal>,
+}
+
+impl ChangeSet {
+    /// Create an empty change set.
+    pub fn new() -> Self {
+        ChangeSet::default()
+    }
+
+    /// Add an edited manifest. Manifests that have not been changed are left out, so that
+    /// applying the change set never rewrites them.
+    pub fn push(&mut self, manifest: LocalManifest) {
+        if manifest.is_modified() {
+            self.manifests.push(manifest);
+        }
+    }
+
+    /// Take back the manifest at `path` if it has been added, to make more edits to it. Each
+    /// manifest has to be edited through a single `LocalManifest`: two of them would each
+    /// overwrite the edits of the other, and wait for each other's lock in `apply`.
+    pub fn take(&mut self, path: &Path) -> Option<LocalManifest> {
+        let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+        let path = canonical(path);
+        let index = self
+            .manifests
+            .iter()
+            .position(|manifest| canonical(manifest.path()) == path)?;
+        Some(self.manifests.remove(index))
+    }
+
+    /// Whether no manifest has been changed.
+    pub fn is_empty(&self) -> bool {
+        self.manifests.is_empty()
+    }
+
+    /// The changed manifests, in the order they were added.
+    pub fn manifests(&self) -> &[LocalManifest] {
+        &self.manifests
+    }
+
+    /// Render the changes to all manifests as unified diffs.
+    pub fn diff(&self) -> String {
+        self.manifests.iter().map(LocalManifest::diff).collect()
+    }
+
+    /// Write the changed manifests to disk. See `LocalManifest::write`.
+    ///
+    /// They are recorded as a single entry in the journal, so that `cargo edit undo` restores all
+    /// of them at once.
+    pub fn apply(&mut self) -> Result<()> {
+        let first = match self.manifests.first() {
+            Some(manifest) => manifest,
+            None => return Ok(()),
+        };
+        for manifest in &self.manifests {
+            manifest.check_edit(&manifest.edited_tables())?;
+        }
+        // Lock all the manifests before writing any, so that either all or none are written if
+        // another process has changed one of them.
+        let mut files = self.manifests
+            .iter()
+            .map(LocalManifest::lock)
+            .collect::<Result<Vec<_>>>()?;
+
+        // Finding the journal runs `cargo metadata`, so it is only done once per change set, and
+        // before anything is written.
+        let journal = Journal::for_manifest(first.path())?;
+        for (manifest, file) in self.manifests.iter().zip(&mut files) {
+            write_file(file, &manifest.contents())
+                .chain_err(|| "Failed to write new manifest contents")?;
+        }
+        let journaled = self.manifests.iter().map(LocalManifest::journaled).collect();
+        journal.record(&JournalEntry::new(journaled))?;
+        self.journal = Some(journal);
+        Ok(())
+    }
+
+    /// Undo `apply`, writing the manifests back as they were read, and removing them from the
+    /// journal.
+    pub fn revert(&self) -> Result<()> {
+        for manifest in &self.manifests {
+            manifest.restore()?;
+        }
+        if let Some(ref journal) = self.journal {
+            journal.forget_last()?;
+        }
+        Ok(())
+    }
+
+    /// Update the lock file after `apply`, for the manifests to be used as they are with
+    /// `--locked`. `crates` are the crates whose requirements have changed, and are updated like
+    /// `cargo update -p <crate>` does. If the dependencies can not be resolved, the changes to
+    /// the manifests are reverted.
+    ///
+    /// Returns the changes to the lock file.
+    pub fn update_lockfile(&self, crates: &[String]) -> Result<Vec<LockChange>> {
+        let manifest = match self.manifests.first() {
+            Some(manifest) => manifest,
+            None => return Ok(Vec::new()),
+        };
+        update_lockfile(manifest.path(), crates).or_else(|err| {
+            self.revert()?;
+            Err(err).chain_err(|| "Failed to update Cargo.lock, the changes have been reverted")
+        })
     }
 }
 
@@ -414,6 +1319,15 @@ mod tests {
     use dependency::Dependency;
     use toml_edit;
 
+    #[test]
+    fn relative_paths() {
+        let rel = |dir, path| relative_path(Path::new(dir), Path::new(path));
+        assert_eq!(rel("/a/b", "/a/c"), PathBuf::from("../c"));
+        assert_eq!(rel("/a/b", "/a/b/c/../d"), PathBuf::from("d"));
+        assert_eq!(rel("/a/b/c", "/a"), PathBuf::from("../.."));
+        assert_eq!(rel("/a", "/a"), PathBuf::from("."));
+    }
+
     #[test]
     fn add_remove_dependency() {
         let mut manifest = Manifest {
@@ -442,7 +1356,7 @@ mod tests {
 
         let new_dep = Dependency::new("cargo-edit").set_version("0.2.0");
         manifest
-            .update_table_entry(&["dependencies".to_owned()], &new_dep, false)
+            .update_table_entry(&["dependencies".to_owned()], &new_dep)
             .unwrap();
     }
 
@@ -459,12 +1373,65 @@ mod tests {
 
         let new_dep = Dependency::new("wrong-dep").set_version("0.2.0");
         manifest
-            .update_table_entry(&["dependencies".to_owned()], &new_dep, false)
+            .update_table_entry(&["dependencies".to_owned()], &new_dep)
             .unwrap();
 
         assert_eq!(manifest.data.to_string(), original.data.to_string());
     }
 
+    #[test]
+    fn upgrade_workspace_dependency() {
+        let mut manifest: Manifest = r#"
+[workspace.dependencies]
+cargo-edit = "0.1.0"
+
+[dependencies]
+cargo-edit = { workspace = true, optional = true }
+"#.parse()
+            .unwrap();
+        let sections = manifest.get_sections();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[1].0, vec!["workspace", "dependencies"]);
+
+        let new_dep = Dependency::new("cargo-edit").set_version("0.2.0");
+        for (section, _) in sections {
+            manifest.update_table_entry(&section, &new_dep).unwrap();
+        }
+
+        // Only the definition is upgraded, the inherited entry is left alone.
+        assert_eq!(
+            manifest.data["workspace"]["dependencies"]["cargo-edit"].as_str(),
+            Some("0.2.0")
+        );
+        assert_eq!(
+            manifest.data["dependencies"]["cargo-edit"]["workspace"].as_bool(),
+            Some(true)
+        );
+        assert!(manifest.data["dependencies"]["cargo-edit"]["version"].is_none());
+    }
+
+    #[test]
+    fn dependency_sections_need_package() {
+        let manifest: Manifest = r#"
+[workspace.dependencies]
+cargo-edit = "0.1.0"
+
+[dependencies]
+cargo-edit = "0.1.0"
+"#.parse()
+            .unwrap();
+        let section = |path: &[&str]| path.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(
+            manifest
+                .check_dependency_section(&section(&["workspace", "dependencies"]))
+                .is_ok()
+        );
+        match manifest.check_dependency_section(&section(&["dependencies"])) {
+            Err(Error(ErrorKind::UnexpectedRootManifest, _)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
     #[test]
     fn remove_dependency_no_section() {
         let mut manifest = Manifest {
@@ -478,6 +1445,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn remove_several_dependencies() {
+        let mut manifest = Manifest {
+            data: toml_edit::Document::new(),
+        };
+        let deps = vec![
+            Dependency::new("cargo-edit").set_version("0.1.0"),
+            Dependency::new("other-dep").set_version("0.1.0"),
+        ];
+        manifest
+            .add_deps(&["dependencies".to_owned()], &deps)
+            .unwrap();
+        manifest
+            .remove_deps(
+                "dependencies",
+                &["cargo-edit".to_owned(), "other-dep".to_owned()],
+            )
+            .unwrap();
+        assert!(manifest.data["dependencies"].is_none());
+    }
+
+    #[test]
+    fn remove_several_dependencies_some_missing() {
+        let mut manifest = Manifest {
+            data: toml_edit::Document::new(),
+        };
+        let dep = Dependency::new("cargo-edit").set_version("0.1.0");
+        manifest
+            .insert_into_table(&["dependencies".to_owned()], &dep)
+            .unwrap();
+        let original = manifest.clone();
+
+        let names = vec![
+            "missing-one".to_owned(),
+            "cargo-edit".to_owned(),
+            "missing-two".to_owned(),
+        ];
+        match manifest.remove_deps("dependencies", &names) {
+            Err(Error(ErrorKind::NonExistentDependencies(missing, _), _)) => {
+                assert_eq!(missing, vec!["missing-one", "missing-two"])
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(manifest.data.to_string(), original.data.to_string());
+    }
+
     #[test]
     fn remove_dependency_non_existent() {
         let mut manifest = Manifest {
@@ -492,4 +1505,221 @@ mod tests {
                 .is_err()
         );
     }
+
+    #[test]
+    fn package_fields() {
+        let manifest: Manifest = r#"[package]
+name = "foo"
+version = "0.1.0"
+authors = ["Alice <alice@example.com>", "Bob"]
+edition = "2018"
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(manifest.package_name(), Some("foo"));
+        assert_eq!(manifest.package_version(), Some("0.1.0"));
+        assert_eq!(
+            manifest.package_authors(),
+            Some(vec!["Alice <alice@example.com>", "Bob"])
+        );
+        assert_eq!(manifest.package_edition(), Some("2018"));
+        assert_eq!(manifest.package_license(), None);
+        assert_eq!(manifest.package_keywords(), None);
+    }
+
+    #[test]
+    fn package_fields_through_project_alias() {
+        let mut manifest: Manifest = "[project]\nname = \"foo\"\n".parse().unwrap();
+        assert_eq!(manifest.package_name(), Some("foo"));
+        manifest.set_package_license("MIT").unwrap();
+        assert_eq!(
+            manifest.data.to_string(),
+            "[project]\nname = \"foo\"\nlicense = \"MIT\"\n"
+        );
+    }
+
+    #[test]
+    fn set_package_fields_preserves_formatting() {
+        let mut manifest: Manifest = r#"[package]
+name   =   "foo"   # the name
+version = "0.1.0"
+keywords = ["a#b", "c"] # search terms
+
+[dependencies]
+"#
+        .parse()
+        .unwrap();
+        manifest.set_package_name("bar").unwrap();
+        manifest
+            .set_package_version(&semver::Version::parse("0.2.0").unwrap())
+            .unwrap();
+        manifest.set_package_keywords(&["cli", "toml"]).unwrap();
+        manifest.set_package_rust_version("1.31").unwrap();
+        assert_eq!(
+            manifest.data.to_string(),
+            r#"[package]
+name   =   "bar"   # the name
+version = "0.2.0"
+keywords = ["cli", "toml"] # search terms
+rust-version = "1.31"
+
+[dependencies]
+"#
+        );
+        assert_eq!(manifest.package_keywords(), Some(vec!["cli", "toml"]));
+    }
+
+    #[test]
+    fn set_workspace_package_version_preserves_formatting() {
+        let mut manifest: Manifest = "[workspace.package]\nversion =  \"0.1.0\" # shared\n"
+            .parse()
+            .unwrap();
+        manifest
+            .set_workspace_package_version(&semver::Version::parse("0.2.0").unwrap())
+            .unwrap();
+        assert_eq!(
+            manifest.data.to_string(),
+            "[workspace.package]\nversion =  \"0.2.0\" # shared\n"
+        );
+    }
+
+    #[test]
+    fn check_edit_of_virtual_manifest() {
+        let manifest: Manifest = "[workspace]\nmembers = []\n".parse().unwrap();
+        assert!(manifest.check_edit(&["workspace", "patch"]).is_ok());
+        assert_eq!(
+            manifest.check_edit(&["patch", "dependencies"]).unwrap_err().to_string(),
+            "Found virtual manifest, but `dependencies` can only be edited in the manifest of an \
+             actual package in this workspace."
+        );
+
+        let package: Manifest = "[package]\nname = \"foo\"\n".parse().unwrap();
+        assert!(package.check_edit(&["dependencies"]).is_ok());
+        let neither: Manifest = "[dependencies]\n".parse().unwrap();
+        assert!(neither.check_edit(&["dependencies"]).is_err());
+    }
+
+    #[test]
+    fn edited_tables() {
+        let original = "[workspace]\nmembers = []\n\n[patch.crates-io]\nfoo = \"1\"\n";
+        let mut manifest = LocalManifest {
+            path: PathBuf::from("Cargo.toml"),
+            manifest: original.parse().unwrap(),
+            original: original.to_string(),
+            format: FileFormat::detect(original),
+        };
+        assert!(manifest.edited_tables().is_empty());
+
+        manifest.data["workspace"]["dependencies"]["bar"] = toml_edit::value("0.2");
+        manifest.data["patch"]["crates-io"]["foo"] = toml_edit::value("2");
+        assert_eq!(manifest.edited_tables(), vec!["patch", "workspace"]);
+        assert!(manifest.check_edit(&manifest.edited_tables()).is_ok());
+
+        manifest.data["dependencies"]["bar"] = toml_edit::value("0.2");
+        assert!(manifest.check_edit(&manifest.edited_tables()).is_err());
+    }
+
+    #[test]
+    fn write_to_path_is_journaled() {
+        let dir = ::tempdir::TempDir::new("cargo-edit-journal").unwrap();
+        let path = dir.path().join("Cargo.toml");
+        let original = "[package]\nname = \"one\"\nversion = \"0.1.0\"\n\n\
+                        [lib]\npath = \"lib.rs\"\n";
+        fs::write(&path, original).unwrap();
+
+        let mut manifest = Manifest::open(&Some(path.clone())).unwrap();
+        manifest.set_package_license("MIT").unwrap();
+        manifest.write_to_path(&path).unwrap();
+
+        let entry = Journal::for_manifest(&path).unwrap().last().unwrap().unwrap();
+        assert_eq!(entry.files.len(), 1);
+        assert_eq!(entry.files[0].original, original);
+        assert_eq!(entry.files[0].written, fs::read_to_string(&path).unwrap());
+    }
+
+    #[test]
+    fn write_keeps_file_format() {
+        let fixture = fs::read_to_string("tests/fixtures/line-endings/Cargo.toml.all").unwrap();
+        let dir = ::tempdir::TempDir::new("cargo-edit-format").unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, &fixture).unwrap();
+        let dep = Dependency::new("docopt").set_version("0.6.0");
+        let expected = format!("{}\r\n\r\n[dependencies]\r\ndocopt = \"0.6.0\"", fixture);
+
+        let mut manifest = Manifest::open(&Some(path.clone())).unwrap();
+        manifest
+            .insert_into_table(&["dependencies".to_owned()], &dep)
+            .unwrap();
+        manifest.write_to_path(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+
+        fs::write(&path, &fixture).unwrap();
+        #[allow(deprecated)]
+        manifest
+            .write_to_file(&mut Manifest::find_file(&Some(path.clone())).unwrap())
+            .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn write_detects_concurrent_changes() {
+        let dir = ::tempdir::TempDir::new("cargo-edit-lock").unwrap();
+        let one = dir.path().join("one.toml");
+        let two = dir.path().join("two.toml");
+        fs::write(&one, "[package]\nname = \"one\"\n").unwrap();
+        fs::write(&two, "[package]\nname = \"two\"\n").unwrap();
+
+        let mut changes = ChangeSet::new();
+        for path in &[&one, &two] {
+            let mut manifest = LocalManifest::try_new(path).unwrap();
+            manifest.set_package_license("MIT").unwrap();
+            changes.push(manifest);
+        }
+        // Another process edits one of the manifests in the meantime.
+        fs::write(&two, "[package]\nname = \"two\"\nedition = \"2018\"\n").unwrap();
+
+        let err = changes.apply().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{} was changed by another process while it was being edited. Run the command \
+                 again to apply the edits on top of those changes.",
+                two.display()
+            )
+        );
+        // Neither manifest has been written.
+        assert_eq!(fs::read_to_string(&one).unwrap(), "[package]\nname = \"one\"\n");
+        assert_eq!(
+            fs::read_to_string(&two).unwrap(),
+            "[package]\nname = \"two\"\nedition = \"2018\"\n"
+        );
+    }
+
+    #[test]
+    fn file_format_round_trip() {
+        for original in &[
+            "[package]\nname = \"foo\"\n",
+            "[package]\r\nname = \"foo\"\r\n",
+            "\u{feff}[package]\nname = \"foo\"\n",
+            "[package]\nname = \"foo\"",
+            "\u{feff}[package]\r\nname = \"foo\"",
+        ] {
+            let format = FileFormat::detect(original);
+            let manifest: Manifest = original.parse().unwrap();
+            assert_eq!(manifest.package_name(), Some("foo"));
+            assert_eq!(&format.apply(&manifest.data.to_string()), original);
+        }
+    }
+
+    #[test]
+    fn file_format_of_new_lines() {
+        let original = "[package]\r\nname = \"foo\"\r\n";
+        let mut manifest: Manifest = original.parse().unwrap();
+        manifest.set_package_version(&semver::Version::new(0, 1, 0)).unwrap();
+        assert_eq!(
+            FileFormat::detect(original).apply(&manifest.data.to_string()),
+            "[package]\r\nname = \"foo\"\r\nversion = \"0.1.0\"\r\n"
+        );
+    }
+
 }
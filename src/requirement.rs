@@ -0,0 +1,287 @@
+//! Compare the version requirements of dependencies.
+use semver;
+use toml_edit;
+
+/// Get the version requirement of a dependency, if it is a plain dependency on a crate from the
+/// default registry. Renamed crates, path, git and inherited dependencies are ignored.
+pub fn registry_requirement(item: &toml_edit::Item) -> Option<&str> {
+    if let Some(version) = item.as_str() {
+        return Some(version);
+    }
+
+    for key in &["path", "git", "package", "registry", "workspace"] {
+        if !item[key].is_none() {
+            return None;
+        }
+    }
+    item["version"].as_str()
+}
+
+/// The lowest version a requirement allows, e.g. `1.2.0` for `^1.2`. This is what requirements are
+/// compared by.
+fn minimum_version(requirement: &str) -> Option<semver::Version> {
+    let version = requirement
+        .trim()
+        .trim_start_matches(|c| "^~=>".contains(c))
+        .trim();
+    let padded = match version.split('.').count() {
+        1 => format!("{}.0.0", version),
+        2 => format!("{}.0", version),
+        _ => version.to_string(),
+    };
+    semver::Version::parse(&padded).ok()
+}
+
+/// The outcome of unifying several requirements on the same crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Unification<'a> {
+    /// All the requirements allow this version, the highest minimum among them. Each can be
+    /// raised to it with `requirement_at_version`, keeping its own operator.
+    Unified(semver::Version),
+    /// Some requirements do not allow the highest one, e.g. `0.2` and `1.0`, or the highest one
+    /// pins an exact version. These are all the requirements, without duplicates.
+    Conflict(Vec<&'a str>),
+}
+
+/// Unify several requirements on the same crate, e.g. those of the members of a workspace, on the
+/// highest minimum version among them. This is only done if all of them already allow it, so that
+/// no requirement is raised across a breaking release, and if the highest one is not an exact pin
+/// (`=`) that the others would be raised to. Requirements that can not be compared (e.g.
+/// `>=1.0, <2.0`) are only checked for allowing it. Returns `None` if none can be compared.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate cargo_edit;
+/// # extern crate semver;
+/// # fn main() {
+///     use cargo_edit::{unify_requirements, Unification};
+///
+///     let requirements = vec!["1.0.80", "^1.0.100", "1"];
+///     assert_eq!(
+///         unify_requirements(requirements.into_iter()),
+///         Some(Unification::Unified(semver::Version::parse("1.0.100").unwrap()))
+///     );
+///     assert_eq!(
+///         unify_requirements(vec!["0.2", "1.0"].into_iter()),
+///         Some(Unification::Conflict(vec!["0.2", "1.0"]))
+///     );
+/// # }
+/// ```
+pub fn unify_requirements<'a, I: Iterator<Item = &'a str>>(
+    requirements: I,
+) -> Option<Unification<'a>> {
+    let mut distinct: Vec<&str> = Vec::new();
+    for requirement in requirements {
+        if !distinct.contains(&requirement) {
+            distinct.push(requirement);
+        }
+    }
+
+    let (highest_minimum, highest) = distinct
+        .iter()
+        .filter_map(|&requirement| minimum_version(requirement).map(|v| (v, requirement)))
+        .max_by(|a, b| a.0.cmp(&b.0))?;
+
+    let pinned = highest.trim().starts_with('=') && distinct.len() > 1;
+    let allowed = |requirement: &str| match semver::VersionReq::parse(requirement) {
+        Ok(parsed) => parsed.matches(&highest_minimum),
+        // Requirements Cargo can not parse either are left for it to report.
+        Err(_) => true,
+    };
+    if pinned || !distinct.iter().all(|requirement| allowed(requirement)) {
+        Some(Unification::Conflict(distinct))
+    } else {
+        Some(Unification::Unified(highest_minimum))
+    }
+}
+
+/// Raise a requirement to exactly `version`, keeping its operator, e.g. `~1.2` becomes `~1.2.5`
+/// for version `1.2.5`. Returns `None` if the requirement does not match the version, or can not
+/// be expressed with a single operator (e.g. `>=1.0, <2.0` or `1.*`).
+///
+/// # Examples
+///
+/// ```
+/// # extern crate cargo_edit;
+/// # extern crate semver;
+/// # fn main() {
+///     use cargo_edit::requirement_at_version;
+///
+///     let version = semver::Version::parse("1.0.80").unwrap();
+///     assert_eq!(requirement_at_version("^1.0", &version), Some("^1.0.80".to_string()));
+///     assert_eq!(requirement_at_version("2", &version), None);
+/// # }
+/// ```
+pub fn requirement_at_version(requirement: &str, version: &semver::Version) -> Option<String> {
+    let requirement = requirement.trim();
+    let rest = requirement
+        .trim_start_matches(|c| "^~=".contains(c))
+        .trim_start();
+    if rest.is_empty() || rest.contains(|c| ",<>*xX".contains(c)) {
+        return None;
+    }
+    let matches = semver::VersionReq::parse(requirement)
+        .map(|parsed| parsed.matches(version))
+        .unwrap_or(false);
+    if !matches {
+        return None;
+    }
+
+    let operator = &requirement[..requirement.len() - rest.len()];
+    Some(format!("{}{}", operator, version))
+}
+
+/// The highest of `versions` that `requirement` allows, e.g. `1.9.0` for `^1.2` out of `2.0.0`,
+/// `1.9.0` and `1.2.0`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate cargo_edit;
+/// # extern crate semver;
+/// # fn main() {
+///     use cargo_edit::highest_matching_version;
+///
+///     let versions = ["2.0.0", "1.9.0", "1.2.0"]
+///         .iter()
+///         .map(|v| semver::Version::parse(v).unwrap())
+///         .collect::<Vec<_>>();
+///     assert_eq!(highest_matching_version("^1.2", &versions), Some(&versions[1]));
+///     assert_eq!(highest_matching_version("3", &versions), None);
+/// # }
+/// ```
+pub fn highest_matching_version<'a>(
+    requirement: &str,
+    versions: &'a [semver::Version],
+) -> Option<&'a semver::Version> {
+    let requirement = semver::VersionReq::parse(requirement).ok()?;
+    versions
+        .iter()
+        .filter(|version| requirement.matches(version))
+        .max()
+}
+
+/// How replacing a version requirement by another one changes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RequirementChange {
+    /// The new requirement does not raise the minimum version
+    Unchanged,
+    /// The old requirement already allows the new minimum version, e.g. `1.2` to `1.9`
+    Compatible,
+    /// The old requirement does not allow the new minimum version, e.g. `1.2` to `2.0`
+    Breaking,
+}
+
+/// Compare a requirement to the one it would be upgraded to. Requirements whose minimum version
+/// can not be determined (e.g. `>=1.0, <2.0`) are only unchanged if they are equal, and are
+/// considered breaking if the old one does not parse.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate cargo_edit;
+/// # fn main() {
+///     use cargo_edit::{requirement_change, RequirementChange};
+///
+///     assert_eq!(requirement_change("^1.2", "1.9.0"), RequirementChange::Compatible);
+///     assert_eq!(requirement_change("1.2", "2.0.0"), RequirementChange::Breaking);
+///     assert_eq!(requirement_change("1.2.3", "1.2"), RequirementChange::Unchanged);
+/// # }
+/// ```
+pub fn requirement_change(old: &str, new: &str) -> RequirementChange {
+    let new_minimum = minimum_version(new);
+    match (minimum_version(old), &new_minimum) {
+        (Some(ref old_minimum), Some(ref new_minimum)) if new_minimum <= old_minimum => {
+            return RequirementChange::Unchanged
+        }
+        _ if old.trim() == new.trim() => return RequirementChange::Unchanged,
+        _ => {}
+    }
+
+    let compatible = match (semver::VersionReq::parse(old), new_minimum) {
+        (Ok(old), Some(new_minimum)) => old.matches(&new_minimum),
+        _ => false,
+    };
+    if compatible {
+        RequirementChange::Compatible
+    } else {
+        RequirementChange::Breaking
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unify() {
+        let unify = |requirements: Vec<&'static str>| unify_requirements(requirements.into_iter());
+        let unified = |version| {
+            Some(Unification::Unified(
+                semver::Version::parse(version).unwrap(),
+            ))
+        };
+        assert_eq!(unify(vec!["0.2.28", "^0.2.40", "0.2"]), unified("0.2.40"));
+        assert_eq!(unify(vec!["1", "~1.0.80", ">=1.0, <2.0"]), unified("1.0.80"));
+        assert_eq!(
+            unify(vec!["0.9", "1.0", "0.9"]),
+            Some(Unification::Conflict(vec!["0.9", "1.0"]))
+        );
+        assert_eq!(
+            unify(vec!["~1.2", "1.5"]),
+            Some(Unification::Conflict(vec!["~1.2", "1.5"]))
+        );
+        assert_eq!(
+            unify(vec!["1.2", "=1.2.3"]),
+            Some(Unification::Conflict(vec!["1.2", "=1.2.3"]))
+        );
+        assert_eq!(unify(vec!["=1.2.3", "=1.2.3"]), unified("1.2.3"));
+        assert_eq!(unify(vec!["not a version"]), None);
+    }
+
+    #[test]
+    fn registry_requirements() {
+        let manifest: toml_edit::Document = r#"
+a = "1.0"
+b = { version = "0.2", features = ["c"] }
+c = { version = "0.3", path = "../c" }
+d = { workspace = true }
+"#.parse()
+            .unwrap();
+        let requirements = manifest
+            .iter()
+            .map(|(_, item)| registry_requirement(item))
+            .collect::<Vec<_>>();
+        assert_eq!(requirements, vec![Some("1.0"), Some("0.2"), None, None]);
+    }
+
+    #[test]
+    fn at_version() {
+        let version = semver::Version::parse("1.2.5").unwrap();
+        let at_version = |requirement| requirement_at_version(requirement, &version);
+        assert_eq!(at_version("1"), Some("1.2.5".to_string()));
+        assert_eq!(at_version("^1.2"), Some("^1.2.5".to_string()));
+        assert_eq!(at_version("~1.2"), Some("~1.2.5".to_string()));
+        assert_eq!(at_version("= 1.2.5"), Some("= 1.2.5".to_string()));
+        assert_eq!(at_version("~1.3"), None);
+        assert_eq!(at_version(">=1.0, <2.0"), None);
+        assert_eq!(at_version("1.*"), None);
+    }
+
+    #[test]
+    fn changes() {
+        assert_eq!(requirement_change("0.2", "0.2.40"), RequirementChange::Compatible);
+        assert_eq!(requirement_change("0.2", "0.3.0"), RequirementChange::Breaking);
+        assert_eq!(requirement_change("~1.2", "1.3.0"), RequirementChange::Breaking);
+        assert_eq!(requirement_change("1.0", "1.0.0"), RequirementChange::Unchanged);
+        assert_eq!(requirement_change("2.0", "1.9.0"), RequirementChange::Unchanged);
+        assert_eq!(
+            requirement_change(">=1.0, <2.0", ">=1.0, <2.0"),
+            RequirementChange::Unchanged
+        );
+        assert_eq!(requirement_change(">=1.0, <2.0", "1.5.0"), RequirementChange::Compatible);
+        assert_eq!(requirement_change(">=1.0, <2.0", "2.1.0"), RequirementChange::Breaking);
+    }
+}
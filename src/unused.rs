@@ -0,0 +1,387 @@
+//! Find dependencies that none of the source files of a package refer to.
+//!
+//! A dependency counts as used if a source file mentions its library name in an `extern crate`, a
+//! `use` declaration, a path such as `serde_json::to_string` or a macro invocation such as
+//! `lazy_static!`. Crates that are used without being named in the code (e.g. `-sys` crates that
+//! only link a library) can be listed in the manifest to skip them:
+//!
+//! ```toml
+//! [package.metadata.cargo-edit]
+//! ignore-unused = ["openssl-sys"]
+//! ```
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::{self, Regex};
+use toml_edit;
+
+use errors::*;
+use manifest::Manifest;
+
+/// Directories holding the sources of the targets Cargo discovers automatically.
+const SOURCE_DIRS: [&str; 4] = ["src", "tests", "examples", "benches"];
+
+/// Sections of target specifications that can set a source path, e.g. `[[bin]]`.
+const TARGET_KEYS: [&str; 5] = ["lib", "bin", "example", "test", "bench"];
+
+/// A dependency that no source file refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnusedDependency {
+    /// Path to the section containing the dependency, e.g. `["target", "cfg(unix)",
+    /// "dependencies"]`
+    pub section: Vec<String>,
+    /// The name of the dependency, as it is written in the manifest
+    #[serde(rename = "crate")]
+    pub name: String,
+}
+
+/// The name the code of a package uses for a dependency. A renamed dependency is known by its key
+/// in the manifest rather than by its package name, and `-` can not be part of an identifier.
+fn library_name(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+/// Whether `source` refers to the crate with the library name `name`.
+///
+/// Line comments are skipped, but doc comments are not, as their examples are compiled as tests.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate cargo_edit;
+/// # fn main() {
+///     use cargo_edit::is_referenced;
+///
+///     assert!(is_referenced("extern crate serde_json;", "serde_json"));
+///     assert!(is_referenced("let x = serde_json::to_string(&y);", "serde_json"));
+///     assert!(!is_referenced("use self::serde_json::to_string;", "serde_json"));
+///     assert!(!is_referenced("// use serde_json;", "serde_json"));
+/// # }
+/// ```
+pub fn is_referenced(source: &str, name: &str) -> bool {
+    let name = regex::escape(name);
+    let pattern = format!(
+        concat!(
+            r"(?:^|[^\w:])(?:",
+            r"extern\s+crate\s+{0}\b|",
+            r"use\s+(?:::\s*)?{0}\b|",
+            r"(?:::\s*)?{0}\s*(?:::|!\s*[(\[{{])",
+            r")"
+        ),
+        name
+    );
+    let pattern = Regex::new(&pattern).expect("library names are escaped");
+
+    source
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            !line.starts_with("//") || line.starts_with("///") || line.starts_with("//!")
+        })
+        .any(|line| pattern.is_match(line))
+}
+
+/// The package table, `[package]` or its older alias `[project]`.
+fn package_table(manifest: &Manifest) -> &toml_edit::Item {
+    if manifest.data["package"].is_none() {
+        &manifest.data["project"]
+    } else {
+        &manifest.data["package"]
+    }
+}
+
+/// The library names of the dependencies listed in `[package.metadata.cargo-edit]` as
+/// `ignore-unused`.
+fn ignored(manifest: &Manifest) -> Vec<String> {
+    package_table(manifest)["metadata"]["cargo-edit"]["ignore-unused"]
+        .as_array()
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|name| name.as_str())
+                .map(library_name)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Collect the Rust source files below `dir`.
+fn collect_sources(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).chain_err(|| format!("Failed to read directory {}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .chain_err(|| format!("Failed to read directory {}", dir.display()))?
+            .path();
+        if path.is_dir() {
+            collect_sources(&path, files)?;
+        } else if path.extension().map(|ext| ext == "rs").unwrap_or(false) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The source files of a package: those of its targets, and those of its build script.
+fn package_sources(
+    manifest: &Manifest,
+    package_dir: &Path,
+) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let build_script = match package_table(manifest)["build"].as_value() {
+        Some(build) if build.as_bool() == Some(false) => None,
+        Some(build) => build.as_str().map(|path| package_dir.join(path)),
+        None => Some(package_dir.join("build.rs")),
+    };
+    let mut code = Vec::new();
+    for dir in &SOURCE_DIRS {
+        let dir = package_dir.join(dir);
+        if dir.is_dir() {
+            collect_sources(&dir, &mut code)?;
+        }
+    }
+    // Targets can live outside of the directories Cargo looks in.
+    for key in &TARGET_KEYS {
+        let target = &manifest.data[*key];
+        let paths = match target.as_array_of_tables() {
+            Some(targets) => targets
+                .iter()
+                .filter_map(|target| target.get("path").and_then(|path| path.as_str()))
+                .collect(),
+            None => target["path"].as_str().into_iter().collect::<Vec<_>>(),
+        };
+        for path in paths {
+            let path = package_dir.join(path);
+            if path.exists() && !code.contains(&path) {
+                code.push(path);
+            }
+        }
+    }
+
+    let mut build = Vec::new();
+    if let Some(build_script) = build_script.filter(|path| path.exists()) {
+        // A build script in a directory of its own may consist of several files, but one that
+        // shares a directory with the targets, like `src/build.rs`, is only that file.
+        match build_script.parent() {
+            Some(dir) if dir != package_dir && !code.iter().any(|path| path.starts_with(dir)) => {
+                collect_sources(dir, &mut build)?
+            }
+            _ => build.push(build_script),
+        }
+    }
+    code.retain(|path| !build.contains(path));
+
+    Ok((code, build))
+}
+
+fn read_sources(paths: &[PathBuf]) -> Result<Vec<String>> {
+    paths
+        .iter()
+        .map(|path| {
+            fs::read_to_string(path).chain_err(|| format!("Failed to read {}", path.display()))
+        })
+        .collect()
+}
+
+/// Find the dependencies of a manifest that none of the given sources refer to. Build
+/// dependencies are looked for in the sources of the build script, all others in the sources of
+/// the package's targets.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate cargo_edit;
+/// # fn main() {
+///     use cargo_edit::{unused_in, Manifest};
+///
+///     let manifest: Manifest = "[dependencies]\nserde = \"1\"\nserde-json = \"1\""
+///         .parse()
+///         .unwrap();
+///     let code = vec!["extern crate serde_json;".to_string()];
+///     let unused = unused_in(&manifest, &code, &[]);
+///     assert_eq!(unused.len(), 1);
+///     assert_eq!(unused[0].name, "serde");
+/// # }
+/// ```
+pub fn unused_in(manifest: &Manifest, code: &[String], build: &[String]) -> Vec<UnusedDependency> {
+    let ignored = ignored(manifest);
+    let mut unused = Vec::new();
+
+    for (section, table) in manifest.get_sections() {
+        let kind = section.last().expect("sections are never empty");
+        // Dependencies shared by a workspace are used by its members, not by the root package.
+        if section[0] == "workspace" {
+            continue;
+        }
+        let sources = if kind == "build-dependencies" {
+            build
+        } else {
+            code
+        };
+        let table = table.as_table_like().expect("Unexpected non-table");
+
+        for (name, _) in table.iter() {
+            let library = library_name(name);
+            if ignored.contains(&library)
+                || sources.iter().any(|source| is_referenced(source, &library))
+            {
+                continue;
+            }
+            unused.push(UnusedDependency {
+                section: section.clone(),
+                name: name.to_string(),
+            });
+        }
+    }
+
+    unused
+}
+
+/// Find the dependencies of the package in `package_dir` that none of its source files refer to.
+pub fn find_unused(manifest: &Manifest, package_dir: &Path) -> Result<Vec<UnusedDependency>> {
+    let (code, build) = package_sources(manifest, package_dir)?;
+    Ok(unused_in(manifest, &read_sources(&code)?, &read_sources(&build)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(unused: &[UnusedDependency]) -> Vec<String> {
+        unused
+            .iter()
+            .map(|dep| format!("{}:{}", dep.section.join("."), dep.name))
+            .collect()
+    }
+
+    #[test]
+    fn references() {
+        assert!(is_referenced("#[macro_use]\nextern crate error_chain;", "error_chain"));
+        assert!(is_referenced("use ::regex::Regex;", "regex"));
+        assert!(is_referenced("use regex;", "regex"));
+        assert!(is_referenced("let re = regex::Regex::new(\"\");", "regex"));
+        assert!(is_referenced("#[derive(serde::Serialize)]", "serde"));
+        assert!(is_referenced("lazy_static! {", "lazy_static"));
+        assert!(is_referenced("/// let x = semver::Version::parse(\"1.0.0\");", "semver"));
+
+        assert!(!is_referenced("use crate::regex::Regex;", "regex"));
+        assert!(!is_referenced("let regex = 1;", "regex"));
+        assert!(!is_referenced("let serde = a != b;", "serde"));
+        assert!(!is_referenced("  // extern crate regex;", "regex"));
+        assert!(!is_referenced("use regex_syntax;", "regex"));
+    }
+
+    #[test]
+    fn unused_per_section() {
+        let manifest: Manifest = r#"
+[package]
+name = "foo"
+
+[dependencies]
+docopt = "1"
+json = { version = "1", package = "serde_json" }
+rand = "0.5"
+
+[dev-dependencies]
+assert-cli = "0.6"
+tempdir = "0.3"
+
+[build-dependencies]
+cc = "1"
+docopt = "1"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+"#
+            .parse()
+            .unwrap();
+        let code = vec![
+            "extern crate docopt;\nuse json::Value;".to_string(),
+            "extern crate assert_cli;".to_string(),
+        ];
+        let build = vec!["fn main() { cc::Build::new(); }".to_string()];
+        assert_eq!(
+            names(&unused_in(&manifest, &code, &build)),
+            vec![
+                "dev-dependencies:tempdir",
+                "build-dependencies:docopt",
+                "dependencies:rand",
+                "target.cfg(unix).dependencies:libc",
+            ]
+        );
+    }
+
+    #[test]
+    fn ignore_list() {
+        let manifest: Manifest = r#"
+[package]
+name = "foo"
+
+[package.metadata.cargo-edit]
+ignore-unused = ["openssl-sys"]
+
+[dependencies]
+openssl-sys = "0.9"
+rand = "0.5"
+"#
+            .parse()
+            .unwrap();
+        assert_eq!(
+            names(&unused_in(&manifest, &[], &[])),
+            vec!["dependencies:rand"]
+        );
+    }
+
+    #[test]
+    fn sources() {
+        let dir = ::tempdir::TempDir::new("cargo-edit-unused").unwrap();
+        let manifest: Manifest = "[package]\nname = \"foo\"\nbuild = \"build/main.rs\"\n\n\
+                                  [[bin]]\nname = \"tool\"\npath = \"tool.rs\"\n"
+            .parse()
+            .unwrap();
+        for file in &["src/lib.rs", "src/a/mod.rs", "tests/it.rs", "build/main.rs", "tool.rs"] {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let (mut code, build) = package_sources(&manifest, dir.path()).unwrap();
+        code.sort();
+        let relative = |paths: Vec<PathBuf>| {
+            paths
+                .iter()
+                .map(|path| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            relative(code),
+            vec![
+                PathBuf::from("src/a/mod.rs"),
+                PathBuf::from("src/lib.rs"),
+                PathBuf::from("tests/it.rs"),
+                PathBuf::from("tool.rs"),
+            ]
+        );
+        assert_eq!(relative(build), vec![PathBuf::from("build/main.rs")]);
+    }
+
+    #[test]
+    fn build_script_next_to_targets() {
+        let dir = ::tempdir::TempDir::new("cargo-edit-unused").unwrap();
+        let manifest: Manifest = "[package]\nname = \"foo\"\nbuild = \"src/build.rs\"\n"
+            .parse()
+            .unwrap();
+        for file in &["src/lib.rs", "src/a/mod.rs", "src/build.rs"] {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let (mut code, build) = package_sources(&manifest, dir.path()).unwrap();
+        code.sort();
+        assert_eq!(
+            code,
+            vec![dir.path().join("src/a/mod.rs"), dir.path().join("src/lib.rs")]
+        );
+        assert_eq!(build, vec![dir.path().join("src/build.rs")]);
+    }
+}
@@ -5,7 +5,10 @@ extern crate toml_edit;
 
 use std::process;
 mod utils;
-use utils::{clone_out_test, execute_command, get_toml};
+use utils::{
+    clone_out_test, copy_package_fixture, copy_workspace_fixture, copy_workspace_test,
+    execute_command, get_toml,
+};
 
 /// Some of the tests need to have a crate name that does not exist on crates.io. Hence this rather
 /// silly constant. Tests _will_ fail, though, if a crate is ever published with this name.
@@ -118,16 +121,14 @@ fn adds_dev_build_dependency() {
     assert!(toml["dev-dependencies"].is_none());
     assert!(toml["build-dependencies"].is_none());
 
-    execute_command(&["add", "my-dev-package", "--dev"], &manifest);
+    // The manifest has to stay valid for Cargo, as the second command looks up its journal.
+    execute_command(&["add", "my-dev-package@0.1.0", "--dev"], &manifest);
     execute_command(&["add", "my-build-package", "--build"], &manifest);
 
     // dependency present afterwards
     let toml = get_toml(&manifest);
     let val = &toml["dev-dependencies"]["my-dev-package"];
-    assert_eq!(
-        val.as_str().unwrap(),
-        "my-dev-package--CURRENT_VERSION_TEST"
-    );
+    assert_eq!(val.as_str().unwrap(), "0.1.0");
     let val = &toml["build-dependencies"]["my-build-package"];
     assert_eq!(
         val.as_str().unwrap(),
@@ -156,8 +157,9 @@ fn adds_multiple_dev_build_dependencies() {
     assert!(toml["build-dependencies"].is_none());
     assert!(toml["build-dependencies"].is_none());
 
+    // The manifest has to stay valid for Cargo, as the second command looks up its journal.
     execute_command(
-        &["add", "my-dev-package1", "my-dev-package2", "--dev"],
+        &["add", "my-dev-package1@0.1.0", "my-dev-package2@0.2.0", "--dev"],
         &manifest,
     );
     execute_command(
@@ -168,15 +170,9 @@ fn adds_multiple_dev_build_dependencies() {
     // dependencies present afterwards
     let toml = get_toml(&manifest);
     let val = &toml["dev-dependencies"]["my-dev-package1"];
-    assert_eq!(
-        val.as_str().unwrap(),
-        "my-dev-package1--CURRENT_VERSION_TEST"
-    );
+    assert_eq!(val.as_str().unwrap(), "0.1.0");
     let val = &toml["dev-dependencies"]["my-dev-package2"];
-    assert_eq!(
-        val.as_str().unwrap(),
-        "my-dev-package2--CURRENT_VERSION_TEST"
-    );
+    assert_eq!(val.as_str().unwrap(), "0.2.0");
     let val = &toml["build-dependencies"]["my-build-package1"];
     assert_eq!(
         val.as_str().unwrap(),
@@ -840,6 +836,7 @@ fn no_argument() {
         .is(r"Invalid arguments.
 
 Usage:
+    cargo add --from <manifest> [<crates>...] [--dev|--build] [options]
     cargo add <crate> [--dev|--build|--optional] [options]
     cargo add <crates>... [--dev|--build|--optional] [options]
     cargo add (-h|--help)
@@ -856,6 +853,7 @@ fn unknown_flags() {
         .is(r"Unknown flag: '--flag'
 
 Usage:
+    cargo add --from <manifest> [<crates>...] [--dev|--build] [options]
     cargo add <crate> [--dev|--build|--optional] [options]
     cargo add <crates>... [--dev|--build|--optional] [options]
     cargo add (-h|--help)
@@ -937,6 +935,291 @@ fn add_prints_message_for_build_deps() {
         .unwrap();
 }
 
+#[test]
+fn add_dry_run_diff() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    let original = get_toml(&manifest).to_string();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-add",
+        "add",
+        "docopt",
+        "--vers=0.6.0",
+        "--dry-run",
+        "--diff",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .contains("Starting dry run. Changes will not be saved.")
+        .and()
+        .stdout()
+        .contains(format!("--- {0}\n+++ {0}\n", manifest).as_str())
+        .and()
+        .stdout()
+        .contains("+[dependencies]\n+docopt = \"0.6.0\"")
+        .unwrap();
+
+    assert_eq!(get_toml(&manifest).to_string(), original);
+}
+
+#[test]
+fn add_diff_writes_changes() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-add",
+        "add",
+        "docopt",
+        "--vers=0.6.0",
+        "--diff",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .contains("+docopt = \"0.6.0\"")
+        .unwrap();
+
+    assert_eq!(
+        get_toml(&manifest)["dependencies"]["docopt"].as_str(),
+        Some("0.6.0")
+    );
+}
+
+#[test]
+fn add_update_lock() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    let lockfile = std::path::Path::new(&manifest).with_file_name("Cargo.lock");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-add",
+        "add",
+        "docopt",
+        "--vers=0.8",
+        "--update-lock",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .contains("Adding docopt v0.8.")
+        .and()
+        .stdout()
+        .contains("in Cargo.lock")
+        .unwrap();
+
+    let locked = std::fs::read_to_string(lockfile).unwrap();
+    assert!(locked.contains("name = \"docopt\"\nversion = \"0.8."));
+}
+
+#[test]
+fn add_update_lock_reverts_on_failure() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    let original = get_toml(&manifest).to_string();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-add",
+        "add",
+        BOGUS_CRATE_NAME,
+        "--vers=1.0",
+        "--update-lock",
+        &format!("--manifest-path={}", manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .contains("Failed to update Cargo.lock, the changes have been reverted")
+        .unwrap();
+
+    assert_eq!(get_toml(&manifest).to_string(), original);
+}
+
+#[test]
+fn add_workspace_dep_to_virtual_manifest() {
+    let (_tmpdir, root_manifest, _workspace_manifests) = copy_workspace_test();
+    let original = get_toml(&root_manifest).to_string();
+
+    // The shared definition could go into the virtual manifest, but inheriting it can not.
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-add",
+        "add",
+        "libc",
+        "--vers=0.2",
+        "--workspace-dep",
+        &format!("--manifest-path={}", root_manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .contains(
+            "Found virtual manifest, but `dependencies` can only be edited in the manifest of \
+             an actual package in this workspace.",
+        )
+        .unwrap();
+
+    assert_eq!(get_toml(&root_manifest).to_string(), original);
+}
+
+#[test]
+fn add_to_virtual_manifest() {
+    let (_tmpdir, root_manifest, _workspace_manifests) = copy_workspace_test();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-add",
+        "add",
+        "docopt",
+        "--vers=0.8",
+        &format!("--manifest-path={}", root_manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .contains(
+            "= help: use `--package <name>` to add the crate to a member of the workspace, or \
+             `--workspace` to add it to every member",
+        )
+        .and()
+        .stdout()
+        .is("")
+        .unwrap();
+}
+
+#[test]
+fn add_to_virtual_manifest_before_fetching() {
+    let (_tmpdir, root_manifest, _workspace_manifests) = copy_workspace_test();
+
+    // Without a version, the latest one would be looked up on crates.io. The manifest is checked
+    // first.
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-add",
+        "add",
+        "docopt",
+        &format!("--manifest-path={}", root_manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .contains("Found virtual manifest")
+        .and()
+        .stderr()
+        .doesnt_contain("crates.io")
+        .unwrap();
+}
+
+#[test]
+fn adds_dependency_to_package() {
+    let (_tmpdir, root_manifest, workspace_manifests) = copy_workspace_test();
+
+    execute_command(
+        &["add", "docopt", "--vers", "0.8", "--package", "two"],
+        &root_manifest,
+    );
+
+    assert_eq!(
+        get_toml(&workspace_manifests[1])["dependencies"]["docopt"].as_str(),
+        Some("0.8")
+    );
+    assert!(get_toml(&workspace_manifests[0])["dependencies"]["docopt"].is_none());
+}
+
+#[test]
+fn adds_dependency_to_workspace() {
+    let (_tmpdir, root_manifest, workspace_manifests) = copy_workspace_test();
+
+    execute_command(
+        &["add", "docopt", "--vers", "0.8", "--dev", "--workspace"],
+        &root_manifest,
+    );
+
+    for manifest in &workspace_manifests {
+        assert_eq!(
+            get_toml(manifest)["dev-dependencies"]["docopt"].as_str(),
+            Some("0.8")
+        );
+    }
+}
+
+#[test]
+fn adds_workspace_dependency() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-deps", &["one", "two"]);
+
+    execute_command(
+        &["add", "docopt", "--vers", "0.8", "--optional", "--workspace-dep"],
+        &workspace_manifests[0],
+    );
+
+    // The definition goes to the root manifest, without `optional`...
+    let root = get_toml(&root_manifest);
+    assert_eq!(
+        root["workspace"]["dependencies"]["docopt"].as_str(),
+        Some("0.8")
+    );
+
+    // ... and the member inherits it.
+    let member = get_toml(&workspace_manifests[0]);
+    let val = &member["dependencies"]["docopt"];
+    assert_eq!(val["workspace"].as_bool(), Some(true));
+    assert_eq!(val["optional"].as_bool(), Some(true));
+    assert!(val["version"].is_none());
+}
+
+#[test]
+fn adds_workspace_path_dependency() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-deps", &["one", "two"]);
+
+    execute_command(
+        &["add", "two", "--path", "../two", "--workspace-dep"],
+        &workspace_manifests[0],
+    );
+
+    // The path is relative to the member, and has to be relative to the root in its definition.
+    let root = get_toml(&root_manifest);
+    assert_eq!(
+        root["workspace"]["dependencies"]["two"]["path"].as_str(),
+        Some("two")
+    );
+}
+
+#[test]
+fn add_workspace_dep_to_every_member() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-deps", &["one", "two"]);
+
+    // Every member adds the definition to the same root manifest, which is written once.
+    execute_command(
+        &["add", "docopt", "--vers", "0.8", "--workspace", "--workspace-dep"],
+        &root_manifest,
+    );
+
+    let root = get_toml(&root_manifest);
+    assert_eq!(root["workspace"]["dependencies"]["docopt"].as_str(), Some("0.8"));
+    assert_eq!(root["workspace"]["dependencies"]["libc"].as_str(), Some("0.2.28"));
+    for manifest in &workspace_manifests {
+        let toml = get_toml(manifest);
+        assert_eq!(
+            toml["dependencies"]["docopt"]["workspace"].as_bool(),
+            Some(true)
+        );
+    }
+}
+
+#[test]
+fn add_workspace_dependency_prints_message() {
+    let (_tmpdir, _root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-deps", &["one", "two"]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-add",
+        "add",
+        "docopt",
+        "--dev",
+        "--vers=0.8.0",
+        "--workspace-dep",
+        &format!("--manifest-path={}", workspace_manifests[1]),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("Adding docopt v0.8.0 to dev-dependencies (inherited from the workspace)")
+        .unwrap();
+}
+
 #[test]
 #[cfg(feature = "test-external-apis")]
 fn add_typo() {
@@ -956,3 +1239,168 @@ fn add_typo() {
         )
         .unwrap();
 }
+
+#[test]
+fn add_from_manifest() {
+    let (tmpdir, manifest) = copy_package_fixture("add-from");
+    let from = tmpdir.path().join("services/old/Cargo.toml");
+
+    execute_command(&["add", "--from", from.to_str().unwrap()], &manifest);
+
+    let toml = get_toml(&manifest);
+    let dependencies = &toml["dependencies"];
+    assert_eq!(dependencies["docopt"].as_str(), Some("0.8"));
+    assert_eq!(
+        dependencies["json"].as_inline_table().unwrap().to_string().trim(),
+        r#"{ package = "serde_json", version = "1.0", optional = true }"#
+    );
+    assert_eq!(
+        dependencies["common"].as_inline_table().unwrap().to_string().trim(),
+        r#"{ version = "0.1", path = "services/common" }"#
+    );
+    assert_eq!(
+        dependencies["serde"].as_inline_table().unwrap().to_string().trim(),
+        r#"{ version = "1.0", default-features = false, features = ["derive", "std"] }"#
+    );
+    assert_eq!(toml["dev-dependencies"]["tempdir"].as_str(), Some("0.3"));
+    assert_eq!(toml["target"]["cfg(unix)"]["dependencies"]["libc"].as_str(), Some("0.2"));
+}
+
+#[test]
+fn add_from_manifest_selected_crates() {
+    let (tmpdir, manifest) = copy_package_fixture("add-from");
+    let from = tmpdir.path().join("services/old/Cargo.toml");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-add",
+        "add",
+        "--from",
+        from.to_str().unwrap(),
+        "serde_json",
+        "libc",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("Adding serde_json v1.0 to optional dependencies
+      Adding libc v0.2 to dependencies for target `cfg(unix)`")
+        .unwrap();
+
+    let toml = get_toml(&manifest);
+    assert_eq!(toml["dependencies"]["docopt"].as_str(), Some("0.6"));
+    assert!(!toml["dependencies"]["json"].is_none());
+    assert!(toml["dependencies"]["serde"].is_none());
+    assert!(toml["dev-dependencies"].is_none());
+}
+
+#[test]
+fn add_from_manifest_section() {
+    let (tmpdir, manifest) = copy_package_fixture("add-from");
+    let from = tmpdir.path().join("services/old/Cargo.toml");
+
+    execute_command(&["add", "--from", from.to_str().unwrap(), "--dev"], &manifest);
+
+    let toml = get_toml(&manifest);
+    assert_eq!(toml["dev-dependencies"]["tempdir"].as_str(), Some("0.3"));
+    assert_eq!(toml["dependencies"]["docopt"].as_str(), Some("0.6"));
+    assert!(toml["target"].is_none());
+}
+
+#[test]
+fn add_from_manifest_registry_and_git_versions() {
+    let (tmpdir, manifest) = copy_package_fixture("add-from");
+    let from = tmpdir.path().join("services/old/Cargo.toml");
+
+    execute_command(&["add", "--from", from.to_str().unwrap(), "--build"], &manifest);
+
+    let toml = get_toml(&manifest);
+    let dependencies = &toml["build-dependencies"];
+    assert_eq!(
+        dependencies["internal"].as_inline_table().unwrap().to_string().trim(),
+        r#"{ version = "0.2", registry = "company" }"#
+    );
+    assert_eq!(
+        dependencies["fork"].as_inline_table().unwrap().to_string().trim(),
+        r#"{ version = "0.4", git = "https://github.com/example/fork" }"#
+    );
+}
+
+#[test]
+fn add_from_manifest_inherited_dependency() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    let (_workspace, _root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-deps", &["one", "two"]);
+
+    execute_command(&["add", "--from", &workspace_manifests[1]], &manifest);
+
+    let toml = get_toml(&manifest);
+    assert_eq!(
+        toml["dependencies"]["libc"].as_inline_table().unwrap().to_string().trim(),
+        r#"{ version = "0.2.28", optional = true }"#
+    );
+}
+
+#[test]
+fn add_from_manifest_missing_crate() {
+    let (tmpdir, manifest) = copy_package_fixture("add-from");
+    let from = tmpdir.path().join("services/old/Cargo.toml");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-add",
+        "add",
+        "--from",
+        from.to_str().unwrap(),
+        "regex",
+        &format!("--manifest-path={}", manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .contains(
+            format!(
+                "The dependency `regex` could not be found in `{}`.",
+                from.display()
+            ).as_str(),
+        )
+        .unwrap();
+
+    assert_eq!(get_toml(&manifest)["dependencies"]["docopt"].as_str(), Some("0.6"));
+}
+
+#[test]
+fn add_json_messages() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-add",
+        "add",
+        "docopt",
+        "--vers=0.8",
+        "--dev",
+        "--message-format=json",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is(format!(
+            r#"{{"manifest_path":"{}","section":["dev-dependencies"],"crate":"docopt","event":"added","version":"0.8","optional":false,"inherited":false}}"#,
+            manifest
+        ).as_str())
+        .unwrap();
+}
+
+#[test]
+fn add_unknown_message_format() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-add",
+        "add",
+        "docopt",
+        "--message-format=xml",
+        &format!("--manifest-path={}", manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .contains("Unknown message format `xml`. Must be one of `human` or `json`.")
+        .unwrap();
+}
@@ -0,0 +1,377 @@
+extern crate assert_cli;
+#[macro_use]
+extern crate pretty_assertions;
+extern crate tempdir;
+extern crate toml_edit;
+
+mod utils;
+use utils::{
+    clone_out_test, copy_workspace_fixture, copy_workspace_test, execute_command, get_toml,
+};
+
+#[test]
+fn lint_finds_problems() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/lint/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-edit",
+        "edit",
+        "lint",
+        &format!("--manifest-path={}", manifest),
+    ]).fails_with(2)
+        .and()
+        .stdout()
+        .contains("`docopt` in `dependencies` has a wildcard version requirement")
+        .and()
+        .stdout()
+        .contains(
+            "`semver` has conflicting version requirements: `0.7` in `dev-dependencies`, `0.9` \
+             in `dependencies`",
+        )
+        .and()
+        .stdout()
+        .contains(
+            "`local` in `dependencies` is a path dependency without a version, so this crate \
+             can not be published",
+        )
+        .and()
+        .stdout()
+        .contains("`clippy` in `dependencies` is a git dependency that is not pinned to a `rev`")
+        .and()
+        .stdout()
+        .contains(
+            "`regex` in `dev-dependencies` is marked as optional, which dev-dependencies can not \
+             be",
+        )
+        .unwrap();
+}
+
+#[test]
+fn lint_json_output() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/lint/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-edit",
+        "edit",
+        "lint",
+        "--message-format=json",
+        &format!("--manifest-path={}", manifest),
+    ]).fails_with(2)
+        .and()
+        .stdout()
+        .contains(
+            format!(
+                r#"{{"manifest_path":"{}","kind":"wildcard-requirement","section":["dependencies"],"crate":"docopt","message":"`docopt` in `dependencies` has a wildcard version requirement"}}"#,
+                manifest
+            ).as_str(),
+        )
+        .unwrap();
+}
+
+#[test]
+fn lint_clean_manifest() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-edit",
+        "edit",
+        "lint",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("")
+        .unwrap();
+}
+
+#[test]
+fn lint_workspace() {
+    let (_tmpdir, root_manifest, _workspace_manifests) = copy_workspace_test();
+
+    // Member `one` depends on `three` by path only.
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-edit",
+        "edit",
+        "lint",
+        "--all",
+        &format!("--manifest-path={}", root_manifest),
+    ]).fails_with(2)
+        .and()
+        .stdout()
+        .contains("`three` in `dependencies` is a path dependency without a version")
+        .unwrap();
+}
+
+#[test]
+fn unknown_message_format() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/lint/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-edit",
+        "edit",
+        "lint",
+        "--message-format=xml",
+        &format!("--manifest-path={}", manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .is(
+            "Command failed due to unhandled error: Unknown message format `xml`. Must be one of \
+             `human` or `json`.",
+        )
+        .unwrap();
+}
+
+#[test]
+fn hoist_shared_dependencies() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    let three = get_toml(&workspace_manifests[2]).to_string();
+
+    execute_command(&["edit", "hoist"], &root_manifest);
+
+    let root = get_toml(&root_manifest);
+    let shared = &root["workspace"]["dependencies"];
+    assert_eq!(shared["libc"].as_str(), Some("0.2.40"));
+    assert_eq!(shared["serde"].as_str(), Some("1.0.80"));
+    // Only declared by a single member.
+    assert!(shared["docopt"].is_none());
+    assert!(shared["regex"].is_none());
+
+    let one = get_toml(&workspace_manifests[0]);
+    assert_eq!(one["dependencies"]["libc"]["workspace"].as_bool(), Some(true));
+    assert!(one["dependencies"]["libc"]["version"].is_none());
+    assert_eq!(
+        one["dependencies"]["serde"]["features"]
+            .as_array()
+            .map(|a| a.len()),
+        Some(1)
+    );
+    assert_eq!(one["dependencies"]["docopt"].as_str(), Some("0.8"));
+
+    let two = get_toml(&workspace_manifests[1]);
+    assert_eq!(two["dependencies"]["libc"]["optional"].as_bool(), Some(true));
+    assert_eq!(
+        two["dev-dependencies"]["serde"]["workspace"].as_bool(),
+        Some(true)
+    );
+
+    assert_eq!(get_toml(&workspace_manifests[2]).to_string(), three);
+}
+
+#[test]
+fn hoist_skips_incompatible_requirements() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    execute_command(&["add", "libc", "--vers", "1.0"], &workspace_manifests[2]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-edit",
+        "edit",
+        "hoist",
+        &format!("--manifest-path={}", root_manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .contains(
+            "Skipping libc: incompatible requirements in one (0.2.28), two (0.2.40), three (1.0)",
+        )
+        .unwrap();
+
+    // Hoisting `1.0` would force a breaking upgrade on `one` and `two`.
+    let root = get_toml(&root_manifest);
+    assert!(root["workspace"]["dependencies"]["libc"].is_none());
+    assert_eq!(root["workspace"]["dependencies"]["serde"].as_str(), Some("1.0.80"));
+    let one = get_toml(&workspace_manifests[0]);
+    assert_eq!(one["dependencies"]["libc"].as_str(), Some("0.2.28"));
+    let three = get_toml(&workspace_manifests[2]);
+    assert_eq!(three["dependencies"]["libc"].as_str(), Some("1.0"));
+}
+
+#[test]
+fn hoist_skips_requirements_with_different_operators() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    execute_command(&["add", "serde", "--vers", "~1.0"], &workspace_manifests[2]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-edit",
+        "edit",
+        "hoist",
+        &format!("--manifest-path={}", root_manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .contains(
+            "Skipping serde: differing requirements in one (1.0), two (1.0.80), three (~1.0)",
+        )
+        .unwrap();
+
+    // Sharing `1.0.80` or `~1.0.80` would change what one of the members accepts.
+    let root = get_toml(&root_manifest);
+    assert!(root["workspace"]["dependencies"]["serde"].is_none());
+    assert_eq!(root["workspace"]["dependencies"]["libc"].as_str(), Some("0.2.40"));
+    let three = get_toml(&workspace_manifests[2]);
+    assert_eq!(three["dependencies"]["serde"].as_str(), Some("~1.0"));
+}
+
+#[test]
+fn hoist_keeps_requirements_as_written() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    execute_command(&["add", "regex", "--vers", "1"], &workspace_manifests[0]);
+
+    execute_command(&["edit", "hoist"], &root_manifest);
+
+    let root = get_toml(&root_manifest);
+    assert_eq!(root["workspace"]["dependencies"]["regex"].as_str(), Some("1"));
+}
+
+#[test]
+fn hoist_dry_run() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    let originals = workspace_manifests
+        .iter()
+        .chain(Some(&root_manifest))
+        .map(|manifest| get_toml(manifest).to_string())
+        .collect::<Vec<_>>();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-edit",
+        "edit",
+        "hoist",
+        "--dry-run",
+        &format!("--manifest-path={}", root_manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("Starting dry run. Changes will not be saved.
+    Hoisting libc 0.2.40 from one (0.2.28), two (0.2.40)
+    Hoisting serde 1.0.80 from one (1.0), two (1.0.80)")
+        .unwrap();
+
+    for (manifest, original) in workspace_manifests
+        .iter()
+        .chain(Some(&root_manifest))
+        .zip(originals)
+    {
+        assert_eq!(get_toml(manifest).to_string(), original);
+    }
+}
+
+#[test]
+fn hoist_json_messages() {
+    let (tmpdir, root_manifest, _workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-edit",
+        "edit",
+        "hoist",
+        "--dry-run",
+        "--message-format=json",
+        &format!("--manifest-path={}", root_manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is(format!(
+            r#"{{"manifest_path":"{0}","crate":"libc","event":"hoisted","requirement":"0.2.40","members":["one","two"],"requirements":["0.2.28","0.2.40"]}}
+{{"manifest_path":"{0}","crate":"serde","event":"hoisted","requirement":"1.0.80","members":["one","two"],"requirements":["1.0","1.0.80"]}}"#,
+            tmpdir.path().join("Cargo.toml").display()
+        ).as_str())
+        .unwrap();
+}
+
+#[test]
+fn undo_last_command() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    let manifests = workspace_manifests
+        .iter()
+        .chain(Some(&root_manifest))
+        .collect::<Vec<_>>();
+    let originals = manifests
+        .iter()
+        .map(|manifest| get_toml(manifest).to_string())
+        .collect::<Vec<_>>();
+
+    execute_command(&["edit", "hoist"], &root_manifest);
+    execute_command(&["upgrade", "--unify"], &root_manifest);
+    let hoisted = manifests
+        .iter()
+        .map(|manifest| get_toml(manifest).to_string())
+        .collect::<Vec<_>>();
+    assert_ne!(hoisted, originals);
+
+    // `--unify` has nothing left to do after hoisting, so the last command to undo is `hoist`.
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-edit",
+        "edit",
+        "undo",
+        &format!("--manifest-path={}", root_manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .contains("Undone `cargo edit hoist")
+        .unwrap();
+
+    for (manifest, original) in manifests.iter().zip(originals) {
+        assert_eq!(get_toml(manifest).to_string(), original);
+    }
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-edit",
+        "edit",
+        "undo",
+        &format!("--manifest-path={}", root_manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .contains("Nothing to undo")
+        .unwrap();
+}
+
+#[test]
+fn undo_rm_from_workspace() {
+    let (_tmpdir, root_manifest, workspace_manifests) = copy_workspace_test();
+    let originals = workspace_manifests
+        .iter()
+        .map(|manifest| get_toml(manifest).to_string())
+        .collect::<Vec<_>>();
+
+    execute_command(&["rm", "libc", "--workspace"], &root_manifest);
+    execute_command(&["edit", "undo"], &root_manifest);
+
+    // The removal from every member is a single command to undo.
+    for (manifest, original) in workspace_manifests.iter().zip(originals) {
+        assert_eq!(get_toml(manifest).to_string(), original);
+    }
+}
+
+#[test]
+fn undo_refuses_changed_manifests() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+
+    execute_command(&["upgrade", "--unify"], &root_manifest);
+    let mut changed = get_toml(&workspace_manifests[0]).to_string();
+    changed.push_str("# changed by hand\n");
+    std::fs::write(&workspace_manifests[0], &changed).unwrap();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-edit",
+        "edit",
+        "undo",
+        &format!("--manifest-path={}", root_manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .contains("Can not undo `cargo upgrade --unify")
+        .and()
+        .stderr()
+        .contains(workspace_manifests[0].as_str())
+        .unwrap();
+
+    assert_eq!(get_toml(&workspace_manifests[0]).to_string(), changed);
+}
@@ -0,0 +1,160 @@
+extern crate assert_cli;
+#[macro_use]
+extern crate pretty_assertions;
+extern crate serde_json;
+
+use std::process;
+
+mod utils;
+use utils::{clone_out_test, copy_workspace_fixture};
+
+#[test]
+fn list_tree() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/list/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-list",
+        "list",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("cargo-list-test-fixture v0.1.0
+├── dependencies
+│   ├── docopt 0.8
+│   ├── json 1.0 (package: serde_json, optional, features: preserve_order)
+│   ├── common 0.1 (path: ../common)
+│   └── clippy (git: https://github.com/rust-lang/rust-clippy, optional)
+├── target.cfg(unix).dependencies
+│   └── libc 0.2
+├── dev-dependencies
+│   └── tempdir 0.3
+└── build-dependencies
+    └── cc 1.0 (features: parallel)")
+        .unwrap();
+}
+
+#[test]
+fn list_table() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/list/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-list",
+        "list",
+        "--table",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("cargo-list-test-fixture v0.1.0:
+Section                        Crate              Requirement  Source                                         Features        Optional
+dependencies                   docopt             0.8          registry
+dependencies                   json (serde_json)  1.0          registry                                       preserve_order  yes
+dependencies                   common             0.1          path: ../common
+dependencies                   clippy                          git: https://github.com/rust-lang/rust-clippy                  yes
+target.cfg(unix).dependencies  libc               0.2          registry
+dev-dependencies               tempdir            0.3          registry
+build-dependencies             cc                 1.0          registry                                       parallel")
+        .unwrap();
+}
+
+#[test]
+fn list_json_outdated() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/list/Cargo.toml.sample");
+
+    let output = process::Command::new("target/debug/cargo-list")
+        .args([
+            "list",
+            "--outdated",
+            "--message-format=json",
+            &format!("--manifest-path={}", manifest),
+        ])
+        .env("CARGO_IS_TEST", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let messages = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(messages.len(), 7);
+
+    let json = &messages[1];
+    assert_eq!(json["manifest_path"], manifest.as_str());
+    assert_eq!(json["section"], serde_json::json!(["dependencies"]));
+    assert_eq!(json["crate"], "json");
+    assert_eq!(json["package"], "serde_json");
+    assert_eq!(json["requirement"], "1.0");
+    assert_eq!(json["source"], "registry");
+    assert_eq!(json["features"], serde_json::json!(["preserve_order"]));
+    assert_eq!(json["optional"], true);
+    // The test registry only knows versions that no requirement allows.
+    assert_eq!(json["latest"], "serde_json--CURRENT_VERSION_TEST");
+    assert_eq!(json["outdated"], true);
+
+    let clippy = &messages[3];
+    assert_eq!(clippy["source"], "git");
+    assert_eq!(clippy["location"], "https://github.com/rust-lang/rust-clippy");
+    assert!(clippy.get("latest").is_none());
+}
+
+#[test]
+fn list_workspace() {
+    let (_tmpdir, root_manifest, _workspace_manifests) =
+        copy_workspace_fixture("workspace-deps", &["one", "two"]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-list",
+        "list",
+        "--workspace",
+        &format!("--manifest-path={}", root_manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("one v0.1.0
+└── dependencies
+    └── libc (workspace)
+
+two v0.1.0
+└── dependencies
+    └── libc (workspace, optional)")
+        .unwrap();
+}
+
+#[test]
+fn list_package() {
+    let (_tmpdir, root_manifest, _workspace_manifests) =
+        copy_workspace_fixture("workspace-deps", &["one", "two"]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-list",
+        "list",
+        "--package",
+        "two",
+        &format!("--manifest-path={}", root_manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("two v0.1.0
+└── dependencies
+    └── libc (workspace, optional)")
+        .unwrap();
+}
+
+#[test]
+fn unknown_message_format() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/list/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-list",
+        "list",
+        "--message-format=xml",
+        &format!("--manifest-path={}", manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .contains("Unknown message format `xml`. Must be one of `human` or `json`.")
+        .unwrap();
+}
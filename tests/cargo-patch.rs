@@ -0,0 +1,254 @@
+extern crate assert_cli;
+#[macro_use]
+extern crate pretty_assertions;
+extern crate tempdir;
+extern crate toml_edit;
+
+mod utils;
+use utils::{clone_out_test, copy_workspace_test, execute_command, get_toml};
+
+#[test]
+fn patch_with_path() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    execute_command(
+        &["patch", "add", "docopt", "--path", "../docopt"],
+        &manifest,
+    );
+
+    let toml = get_toml(&manifest);
+    let val = &toml["patch"]["crates-io"]["docopt"];
+    assert_eq!(val["path"].as_str(), Some("../docopt"));
+    assert!(!toml.to_string().contains("[patch]\n"));
+}
+
+#[test]
+fn patch_with_git_branch() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    execute_command(
+        &[
+            "patch",
+            "add",
+            "docopt",
+            "--git",
+            "https://github.com/docopt/docopt.rs",
+            "--branch",
+            "fix",
+        ],
+        &manifest,
+    );
+
+    let toml = get_toml(&manifest);
+    let val = &toml["patch"]["crates-io"]["docopt"];
+    assert_eq!(
+        val["git"].as_str(),
+        Some("https://github.com/docopt/docopt.rs")
+    );
+    assert_eq!(val["branch"].as_str(), Some("fix"));
+}
+
+#[test]
+fn patch_infers_git_source() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    execute_command(
+        &["patch", "add", "clippy", "--path", "../clippy"],
+        &manifest,
+    );
+
+    let toml = get_toml(&manifest);
+    let val = &toml["patch"]["https://github.com/Manishearth/rust-clippy.git"]["clippy"];
+    assert_eq!(val["path"].as_str(), Some("../clippy"));
+}
+
+#[test]
+fn patch_with_explicit_source() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    execute_command(
+        &[
+            "patch",
+            "add",
+            "docopt",
+            "--path",
+            "../docopt",
+            "--source",
+            "https://example.com/index",
+        ],
+        &manifest,
+    );
+
+    let toml = get_toml(&manifest);
+    let val = &toml["patch"]["https://example.com/index"]["docopt"];
+    assert_eq!(val["path"].as_str(), Some("../docopt"));
+}
+
+#[test]
+fn replace_with_path() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    execute_command(
+        &[
+            "patch",
+            "add",
+            "docopt@0.6.0",
+            "--replace",
+            "--path",
+            "../docopt",
+        ],
+        &manifest,
+    );
+
+    let toml = get_toml(&manifest);
+    let val = &toml["replace"]["docopt:0.6.0"];
+    assert_eq!(val["path"].as_str(), Some("../docopt"));
+    assert!(val["version"].is_none());
+}
+
+#[test]
+fn refuses_patch_and_replace() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    execute_command(
+        &["patch", "add", "docopt", "--path", "../docopt"],
+        &manifest,
+    );
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-patch",
+        "patch",
+        "add",
+        "pad@0.1.0",
+        "--replace",
+        "--path",
+        "../pad",
+        &format!("--manifest-path={}", manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .is(
+            "Command failed due to unhandled error: Cannot specify both `[patch]` and `[replace]` \
+             in the same manifest. Remove the existing overrides first.",
+        )
+        .unwrap();
+}
+
+#[test]
+fn patch_json_messages() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-patch",
+        "patch",
+        "add",
+        "docopt",
+        "--path",
+        "../docopt",
+        "--message-format=json",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is(format!(
+            r#"{{"manifest_path":"{}","section":["patch","crates-io"],"crate":"docopt","event":"patched"}}"#,
+            manifest
+        ).as_str())
+        .unwrap();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-patch",
+        "patch",
+        "add",
+        "pad@0.1.0",
+        "--replace",
+        "--path",
+        "../pad",
+        "--message-format=json",
+        &format!("--manifest-path={}", manifest),
+    ]).fails_with(1)
+        .and()
+        .stdout()
+        .contains(r#"{"event":"error","message":"Cannot specify both `[patch]` and `[replace]`"#)
+        .unwrap();
+}
+
+#[test]
+fn remove_patch() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+    let original = get_toml(&manifest).to_string();
+
+    execute_command(
+        &["patch", "add", "docopt", "--path", "../docopt"],
+        &manifest,
+    );
+    execute_command(&["patch", "rm", "docopt"], &manifest);
+
+    assert_eq!(get_toml(&manifest).to_string(), original);
+}
+
+#[test]
+fn remove_missing_patch() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-patch",
+        "patch",
+        "rm",
+        "docopt",
+        &format!("--manifest-path={}", manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .is(
+            "Command failed due to unhandled error: The dependency `docopt` could not be found in \
+             `patch`.",
+        )
+        .unwrap();
+}
+
+#[test]
+fn list_patches() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    execute_command(
+        &["patch", "add", "docopt", "--path", "../docopt"],
+        &manifest,
+    );
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-patch",
+        "patch",
+        "list",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is(r#"patch.crates-io: docopt = { path = "../docopt" }"#)
+        .unwrap();
+}
+
+#[test]
+fn patch_workspace_root() {
+    let (_tmpdir, root_manifest, workspace_manifests) = copy_workspace_test();
+    let members = workspace_manifests
+        .iter()
+        .map(|manifest| get_toml(manifest).to_string())
+        .collect::<Vec<_>>();
+
+    // Run against a member, the patch should still end up in the virtual root manifest, with the
+    // path relative to the member rebased onto the root.
+    execute_command(
+        &["patch", "add", "libc", "--path", "../libc"],
+        &workspace_manifests[0],
+    );
+
+    let toml = get_toml(&root_manifest);
+    assert_eq!(
+        toml["patch"]["crates-io"]["libc"]["path"].as_str(),
+        Some("libc")
+    );
+    for (manifest, original) in workspace_manifests.iter().zip(members) {
+        assert_eq!(get_toml(manifest).to_string(), original);
+    }
+}
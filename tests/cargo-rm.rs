@@ -1,7 +1,11 @@
 extern crate assert_cli;
 
+use std::fs;
+use std::path::Path;
+
 mod utils;
-use utils::{clone_out_test, execute_command, get_toml};
+use utils::{clone_out_test, copy_package_fixture, copy_workspace_test, execute_command,
+            get_toml};
 
 #[test]
 fn remove_existing_dependency() {
@@ -14,6 +18,20 @@ fn remove_existing_dependency() {
     assert!(toml["dependencies"]["docopt"].is_none());
 }
 
+#[test]
+fn remove_multiple_existing_dependencies() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    let toml = get_toml(&manifest);
+    assert!(!toml["dependencies"]["docopt"].is_none());
+    assert!(!toml["dependencies"]["semver"].is_none());
+    execute_command(&["rm", "docopt", "semver"], &manifest);
+    let toml = get_toml(&manifest);
+    assert!(toml["dependencies"]["docopt"].is_none());
+    assert!(toml["dependencies"]["semver"].is_none());
+    assert!(!toml["dependencies"]["pad"].is_none());
+}
+
 #[test]
 fn remove_existing_dependency_from_specific_section() {
     let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
@@ -81,11 +99,56 @@ fn invalid_dependency() {
         &format!("--manifest-path={}", manifest),
     ]).fails_with(1)
         .and()
-        .stderr().is(
+        .stderr()
+        .is(format!(
             "Command failed due to unhandled error: The dependency `invalid_dependency_name` could \
-             not be found in `dependencies`.",
-        )
+             not be found in `dependencies`.
+  --> {}:12:1
+   |
+12 | [dependencies]
+   | ^",
+            manifest
+        ).as_str())
+        .unwrap();
+}
+
+#[test]
+fn invalid_dependencies_leave_manifest_untouched() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+    let original = get_toml(&manifest).to_string();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "first_invalid",
+        "docopt",
+        "second_invalid",
+        &format!("--manifest-path={}", manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .is(format!(
+            "Command failed due to unhandled error: The dependencies `first_invalid`, \
+             `second_invalid` could not be found in `dependencies`.
+  --> {}:12:1
+   |
+12 | [dependencies]
+   | ^",
+            manifest
+        ).as_str())
         .unwrap();
+
+    assert_eq!(get_toml(&manifest).to_string(), original);
+}
+
+#[test]
+fn remove_last_dependencies_from_section() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    execute_command(&["rm", "--dev", "regex", "regex"], &manifest);
+
+    let toml = get_toml(&manifest);
+    assert!(toml["dev-dependencies"].is_none());
 }
 
 #[test]
@@ -122,13 +185,67 @@ fn invalid_dependency_in_section() {
     ]).fails_with(1)
         .and()
         .stderr()
-        .is(
+        .is(format!(
             "Command failed due to unhandled error: The dependency `semver` could not be found in \
-             `dev-dependencies`.",
+             `dev-dependencies`.
+  --> {}:20:1
+   |
+20 | [dev-dependencies]
+   | ^
+   = help: `semver` is declared in `build-dependencies`",
+            manifest
+        ).as_str())
+        .unwrap();
+}
+
+#[test]
+fn invalid_manifest() {
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "foo",
+        "--manifest-path=tests/fixtures/manifest-invalid/Cargo.toml.sample",
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .is(
+            "Command failed due to unhandled error: Manifest is not valid TOML
+ --> tests/fixtures/manifest-invalid/Cargo.toml.sample:6:7
+  |
+6 | key = invalid-value
+  |       ^
+  = note: Unexpected `i`
+  = note: Expected `digit`, `-` or `+`",
         )
         .unwrap();
 }
 
+#[test]
+fn rm_from_virtual_manifest() {
+    let (_tmpdir, root_manifest, _workspace_manifests) = copy_workspace_test();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "libc",
+        &format!("--manifest-path={}", root_manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .is(format!(
+            "Command failed due to unhandled error: Found virtual manifest, but this command \
+             requires running against an actual package in this workspace.
+ --> {}:1:1
+  |
+1 | [workspace]
+  | ^
+  = help: use `--package <name>` to remove the crate from a member of the workspace, or \
+             `--workspace` to remove it from every member that depends on it",
+            root_manifest
+        ).as_str())
+        .unwrap();
+}
+
 #[test]
 fn no_argument() {
     assert_cli::Assert::command(&["target/debug/cargo-rm", "rm"])
@@ -138,7 +255,8 @@ fn no_argument() {
         .is(r"Invalid arguments.
 
 Usage:
-    cargo rm <crate> [--dev|--build] [options]
+    cargo rm <crates>... [--dev|--build] [options]
+    cargo rm --unused [--apply] [options]
     cargo rm (-h|--help)
     cargo rm --version")
         .unwrap();
@@ -153,7 +271,8 @@ fn unknown_flags() {
         .is(r"Unknown flag: '--flag'
 
 Usage:
-    cargo rm <crate> [--dev|--build] [options]
+    cargo rm <crates>... [--dev|--build] [options]
+    cargo rm --unused [--apply] [options]
     cargo rm (-h|--help)
     cargo rm --version")
         .unwrap();
@@ -174,3 +293,282 @@ fn rm_prints_message() {
         .is("Removing semver from dependencies")
         .unwrap();
 }
+
+#[test]
+fn rm_prints_messages_for_multiple() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "semver",
+        "docopt",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("Removing semver from dependencies\n    Removing docopt from dependencies")
+        .unwrap();
+}
+
+#[test]
+fn rm_prints_message_once_for_duplicates() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "semver",
+        "semver",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("Removing semver from dependencies")
+        .unwrap();
+}
+
+#[test]
+fn rm_from_package() {
+    let (_tmpdir, root_manifest, workspace_manifests) = copy_workspace_test();
+
+    execute_command(&["rm", "libc", "-p", "two"], &root_manifest);
+
+    assert!(get_toml(&workspace_manifests[1])["dependencies"]["libc"].is_none());
+    assert!(!get_toml(&workspace_manifests[0])["dependencies"]["libc"].is_none());
+}
+
+#[test]
+fn rm_from_workspace() {
+    let (_tmpdir, root_manifest, workspace_manifests) = copy_workspace_test();
+
+    // Only member `one` depends on `three`.
+    execute_command(&["rm", "libc", "three", "--workspace"], &root_manifest);
+
+    for manifest in &workspace_manifests {
+        let toml = get_toml(manifest);
+        assert!(toml["dependencies"]["libc"].is_none());
+        assert!(toml["dependencies"]["three"].is_none());
+    }
+}
+
+#[test]
+fn rm_from_workspace_missing() {
+    let (_tmpdir, root_manifest, workspace_manifests) = copy_workspace_test();
+    let originals = workspace_manifests
+        .iter()
+        .map(|manifest| get_toml(manifest).to_string())
+        .collect::<Vec<_>>();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "libc",
+        "docopt",
+        "--workspace",
+        &format!("--manifest-path={}", root_manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .is(
+            "Command failed due to unhandled error: The dependency `docopt` could not be found in \
+             `dependencies`.",
+        )
+        .unwrap();
+
+    for (manifest, original) in workspace_manifests.iter().zip(originals) {
+        assert_eq!(get_toml(manifest).to_string(), original);
+    }
+}
+
+#[test]
+fn rm_unused_from_workspace_failure_leaves_manifests_untouched() {
+    let (_tmpdir, root_manifest, workspace_manifests) = copy_workspace_test();
+    let originals = workspace_manifests
+        .iter()
+        .map(|manifest| get_toml(manifest).to_string())
+        .collect::<Vec<_>>();
+    // The sources of the last member can not be read, after the others have been edited.
+    let last = Path::new(&workspace_manifests[3]).parent().unwrap();
+    fs::create_dir(last.join("build.rs")).unwrap();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "--unused",
+        "--apply",
+        "--workspace",
+        &format!("--manifest-path={}", root_manifest),
+    ]).fails_with(1)
+        .unwrap();
+
+    for (manifest, original) in workspace_manifests.iter().zip(originals) {
+        assert_eq!(get_toml(manifest).to_string(), original);
+    }
+}
+
+#[test]
+fn rm_from_unknown_package() {
+    let (_tmpdir, root_manifest, _workspace_manifests) = copy_workspace_test();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "libc",
+        "--package=five",
+        &format!("--manifest-path={}", root_manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .is(
+            "Command failed due to unhandled error: The package `five` could not be found in the \
+             workspace.",
+        )
+        .unwrap();
+}
+
+#[test]
+fn rm_dry_run_diff() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+    let original = get_toml(&manifest).to_string();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "semver",
+        "--dry-run",
+        "--diff",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is(format!(
+            "Starting dry run. Changes will not be saved.
+    Removing semver from dependencies
+--- {0}
++++ {0}
+@@ -13,7 +13,6 @@
+ docopt = \"0.6\"
+ pad = \"0.1\"
+ rustc-serialize = \"0.3\"
+-semver = \"0.1\"
+ toml = \"0.1\"
+ clippy = {{git = \"https://github.com/Manishearth/rust-clippy.git\", optional = true}}
+ ",
+            manifest
+        ).as_str())
+        .unwrap();
+
+    assert_eq!(get_toml(&manifest).to_string(), original);
+}
+
+#[test]
+fn rm_unused_reports_dependencies() {
+    let (_tmpdir, manifest) = copy_package_fixture("unused");
+    let original = get_toml(&manifest).to_string();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "--unused",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("      Unused regex in dev-dependencies
+      Unused semver in build-dependencies
+      Unused rand in dependencies
+      Unused libc in target.cfg(unix).dependencies")
+        .unwrap();
+
+    assert_eq!(get_toml(&manifest).to_string(), original);
+}
+
+#[test]
+fn rm_unused_build_script_in_src() {
+    let (tmpdir, manifest) = copy_package_fixture("unused");
+    let package = tmpdir.path();
+    fs::rename(package.join("build.rs"), package.join("src/build.rs")).unwrap();
+    let toml = fs::read_to_string(&manifest).unwrap().replace(
+        "version = \"0.1.0\"\n",
+        "version = \"0.1.0\"\nbuild = \"src/build.rs\"\n",
+    );
+    fs::write(&manifest, toml).unwrap();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "--unused",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("      Unused regex in dev-dependencies
+      Unused semver in build-dependencies
+      Unused rand in dependencies
+      Unused libc in target.cfg(unix).dependencies")
+        .unwrap();
+}
+
+#[test]
+fn rm_unused_apply() {
+    let (_tmpdir, manifest) = copy_package_fixture("unused");
+
+    execute_command(&["rm", "--unused", "--apply"], &manifest);
+
+    let toml = get_toml(&manifest);
+    assert!(toml["dev-dependencies"]["regex"].is_none());
+    assert!(toml["build-dependencies"]["semver"].is_none());
+    assert!(toml["dependencies"]["rand"].is_none());
+    assert!(toml["target"].is_none());
+    for (section, name) in &[
+        ("dependencies", "docopt"),
+        ("dependencies", "json"),
+        ("dependencies", "openssl-sys"),
+        ("dev-dependencies", "assert-cli"),
+        ("build-dependencies", "cc"),
+    ] {
+        assert!(!toml[section][name].is_none(), "{} was removed", name);
+    }
+}
+
+#[test]
+fn rm_json_messages() {
+    let (_tmpdir, manifest) = copy_package_fixture("unused");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "--unused",
+        "--apply",
+        "--message-format=json",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is(format!(
+            r#"{{"manifest_path":"{0}","section":["dev-dependencies"],"crate":"regex","event":"removed"}}
+{{"manifest_path":"{0}","section":["build-dependencies"],"crate":"semver","event":"removed"}}
+{{"manifest_path":"{0}","section":["dependencies"],"crate":"rand","event":"removed"}}
+{{"manifest_path":"{0}","section":["target","cfg(unix)","dependencies"],"crate":"libc","event":"removed"}}"#,
+            manifest
+        ).as_str())
+        .unwrap();
+}
+
+#[test]
+fn rm_json_error() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/rm/Cargo.toml.sample");
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-rm",
+        "rm",
+        "not_a_dependency",
+        "--message-format=json",
+        &format!("--manifest-path={}", manifest),
+    ]).fails_with(1)
+        .and()
+        .stdout()
+        .contains(r#"{"event":"error","message":"The dependency `not_a_dependency` could not be found"#)
+        .unwrap();
+}
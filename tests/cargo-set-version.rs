@@ -0,0 +1,190 @@
+extern crate assert_cli;
+#[macro_use]
+extern crate pretty_assertions;
+extern crate tempdir;
+extern crate toml_edit;
+
+mod utils;
+use utils::{copy_workspace_fixture, execute_command, get_toml};
+
+#[test]
+fn set_version_updates_dependents() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-version", &["one", "two"]);
+
+    execute_command(&["set-version", "0.2.0"], &workspace_manifests[0]);
+
+    let one = get_toml(&workspace_manifests[0]);
+    assert_eq!(one["package"]["version"].as_str(), Some("0.2.0"));
+
+    let two = get_toml(&workspace_manifests[1]);
+    assert_eq!(two["package"]["version"].as_str(), Some("0.3.1"));
+    assert_eq!(
+        two["dependencies"]["one"]["version"].as_str(),
+        Some("0.2.0")
+    );
+    assert_eq!(
+        two["dev-dependencies"]["one"]["version"].as_str(),
+        Some("=0.2.0")
+    );
+
+    let root = get_toml(&root_manifest);
+    assert_eq!(
+        root["workspace"]["dependencies"]["one"]["version"].as_str(),
+        Some("0.2.0")
+    );
+}
+
+#[test]
+fn bump_all() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-version", &["one", "two"]);
+
+    execute_command(&["set-version", "--bump", "minor", "--all"], &root_manifest);
+
+    assert_eq!(
+        get_toml(&workspace_manifests[0])["package"]["version"].as_str(),
+        Some("0.2.0")
+    );
+    assert_eq!(
+        get_toml(&workspace_manifests[1])["package"]["version"].as_str(),
+        Some("0.4.0")
+    );
+}
+
+#[test]
+fn set_version_prints_messages() {
+    let (_tmpdir, _root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-version", &["one", "two"]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-set-version",
+        "set-version",
+        "--bump",
+        "prerelease",
+        &format!("--manifest-path={}", workspace_manifests[0]),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is("Setting one v0.1.0 -> v0.1.1-alpha.1
+    Updating one in workspace.dependencies to 0.1.1-alpha.1
+    Updating one in dev-dependencies to =0.1.1-alpha.1
+    Updating one in dependencies to 0.1.1-alpha.1")
+        .unwrap();
+}
+
+#[test]
+fn set_version_json_messages() {
+    let (tmpdir, _root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-version", &["one", "two"]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-set-version",
+        "set-version",
+        "0.2.0",
+        "--message-format=json",
+        &format!("--manifest-path={}", workspace_manifests[0]),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is(format!(
+            r#"{{"manifest_path":"{0}","crate":"one","event":"version-changed","old":"0.1.0","new":"0.2.0"}}
+{{"manifest_path":"{1}","section":["workspace","dependencies"],"crate":"one","event":"updated","old":"0.1","new":"0.2.0"}}
+{{"manifest_path":"{2}","section":["dev-dependencies"],"crate":"one","event":"updated","old":"=0.1.0","new":"=0.2.0"}}
+{{"manifest_path":"{2}","section":["dependencies"],"crate":"one","event":"updated","old":"0.1.0","new":"0.2.0"}}"#,
+            workspace_manifests[0],
+            tmpdir.path().join("Cargo.toml").display(),
+            workspace_manifests[1]
+        ).as_str())
+        .unwrap();
+}
+
+#[test]
+fn dry_run() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-version", &["one", "two"]);
+    let originals = workspace_manifests
+        .iter()
+        .chain(Some(&root_manifest))
+        .map(|manifest| get_toml(manifest).to_string())
+        .collect::<Vec<_>>();
+
+    execute_command(
+        &["set-version", "--bump", "major", "--all", "--dry-run"],
+        &root_manifest,
+    );
+
+    for (manifest, original) in workspace_manifests
+        .iter()
+        .chain(Some(&root_manifest))
+        .zip(originals)
+    {
+        assert_eq!(get_toml(manifest).to_string(), original);
+    }
+}
+
+#[test]
+fn invalid_bump_level() {
+    let (_tmpdir, _root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-version", &["one", "two"]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-set-version",
+        "set-version",
+        "--bump",
+        "huge",
+        &format!("--manifest-path={}", workspace_manifests[0]),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .is(
+            "Command failed due to unhandled error: Invalid bump level `huge`. Must be one of \
+             `major`, `minor`, `patch` or `prerelease`.",
+        )
+        .unwrap();
+}
+
+#[test]
+fn invalid_bump_level_json() {
+    let (_tmpdir, _root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-version", &["one", "two"]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-set-version",
+        "set-version",
+        "--bump",
+        "huge",
+        "--message-format=json",
+        &format!("--manifest-path={}", workspace_manifests[0]),
+    ]).fails_with(1)
+        .and()
+        .stdout()
+        .contains(r#"{"event":"error","message":"Invalid bump level `huge`."#)
+        .unwrap();
+}
+
+#[test]
+fn detect_workspace() {
+    let (_tmpdir, root_manifest, _workspace_manifests) =
+        copy_workspace_fixture("workspace-version", &["one", "two"]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-set-version",
+        "set-version",
+        "1.0.0",
+        &format!("--manifest-path={}", root_manifest),
+    ]).fails_with(1)
+        .and()
+        .stderr()
+        .is(format!(
+            "Command failed due to unhandled error: Found virtual manifest, but this command \
+             requires running against an actual package in this workspace.
+ --> {}:1:1
+  |
+1 | [workspace]
+  | ^
+  = help: use `--all` to change the version of all packages in the workspace",
+            root_manifest
+        ).as_str())
+        .unwrap();
+}
@@ -4,56 +4,27 @@ extern crate pretty_assertions;
 extern crate tempdir;
 extern crate toml_edit;
 
-use std::fs;
-
 mod utils;
-use utils::{clone_out_test, execute_command, get_toml};
-
-/// Helper function that copies the workspace test into a temporary directory.
-pub fn copy_workspace_test() -> (tempdir::TempDir, String, Vec<String>) {
-    // Create a temporary directory and copy in the root manifest, the dummy rust file, and
-    // workspace member manifests.
-    let tmpdir = tempdir::TempDir::new("upgrade_workspace")
-        .expect("failed to construct temporary directory");
-
-    let (root_manifest_path, workspace_manifest_paths) = {
-        // Helper to copy in files to the temporary workspace. The standard library doesn't have a
-        // good equivalent of `cp -r`, hence this oddity.
-        let copy_in = |dir, file| {
-            let file_path = tmpdir
-                .path()
-                .join(dir)
-                .join(file)
-                .to_str()
-                .unwrap()
-                .to_string();
-
-            fs::create_dir_all(tmpdir.path().join(dir)).unwrap();
-
-            fs::copy(
-                format!("tests/fixtures/workspace/{}/{}", dir, file),
-                &file_path,
-            ).unwrap_or_else(|err| panic!("could not copy test file: {}", err));
-
-            file_path
-        };
-
-        let root_manifest_path = copy_in(".", "Cargo.toml");
-        copy_in(".", "dummy.rs");
-
-        let workspace_manifest_paths = ["one", "two", "implicit/three", "explicit/four"]
-            .iter()
-            .map(|member| copy_in(member, "Cargo.toml"))
-            .collect::<Vec<_>>();
-
-        (root_manifest_path, workspace_manifest_paths)
-    };
+use utils::{
+    clone_out_test, copy_workspace_fixture, copy_workspace_test, execute_command, get_toml,
+};
 
-    (
-        tmpdir,
-        root_manifest_path,
-        workspace_manifest_paths.to_owned(),
-    )
+use std::fs;
+use std::io::Write;
+use std::process;
+use std::time::SystemTime;
+
+/// The contents and modification time of each manifest, to check that they are left untouched.
+fn snapshot(manifests: &[&String]) -> Vec<(Vec<u8>, SystemTime)> {
+    manifests
+        .iter()
+        .map(|manifest| {
+            (
+                fs::read(manifest).unwrap(),
+                fs::metadata(manifest).unwrap().modified().unwrap(),
+            )
+        })
+        .collect()
 }
 
 // Verify that an upgraded Cargo.toml matches what we expect.
@@ -137,6 +108,206 @@ fn upgrade_all_allow_prerelease_dry_run() {
     );
 }
 
+#[test]
+fn upgrade_dry_run_diff() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    execute_command(&["add", "docopt", "--vers", "0.8"], &manifest);
+    let original = get_toml(&manifest).to_string();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-upgrade",
+        "upgrade",
+        "docopt@0.9",
+        "--dry-run",
+        "--diff",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .contains(
+            format!(
+                "--- {0}\n+++ {0}\n@@ -6,4 +6,4 @@\n path = \"dummy.rs\"\n \n [dependencies]\n\
+                 -docopt = \"0.8\"\n\\ No newline at end of file\n\
+                 +docopt = \"0.9\"\n\\ No newline at end of file\n",
+                manifest
+            ).as_str(),
+        )
+        .unwrap();
+
+    assert_eq!(get_toml(&manifest).to_string(), original);
+}
+
+#[test]
+fn upgrade_dry_run_leaves_files_untouched() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-deps", &["one", "two"]);
+    let manifests = workspace_manifests
+        .iter()
+        .chain(Some(&root_manifest))
+        .collect::<Vec<_>>();
+    let before = snapshot(&manifests);
+
+    execute_command(&["upgrade", "--all", "--dry-run"], &root_manifest);
+    execute_command(&["upgrade", "--all", "--dry-run", "--diff"], &root_manifest);
+
+    assert_eq!(snapshot(&manifests), before);
+}
+
+#[test]
+fn upgrade_unify_dry_run_leaves_files_untouched() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    let manifests = workspace_manifests
+        .iter()
+        .chain(Some(&root_manifest))
+        .collect::<Vec<_>>();
+    let before = snapshot(&manifests);
+
+    execute_command(&["upgrade", "--unify", "--dry-run"], &root_manifest);
+
+    assert_eq!(snapshot(&manifests), before);
+}
+
+#[test]
+fn upgrade_does_not_rewrite_unchanged_manifests() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    // Neither `two` nor `three` require an older version than another member.
+    let unchanged = vec![&workspace_manifests[1], &workspace_manifests[2], &root_manifest];
+    let before = snapshot(&unchanged);
+
+    execute_command(&["upgrade", "--unify"], &root_manifest);
+
+    assert_eq!(snapshot(&unchanged), before);
+    assert_eq!(
+        get_toml(&workspace_manifests[0])["dependencies"]["libc"].as_str(),
+        Some("0.2.40")
+    );
+}
+
+/// Create the lock file of the workspace `manifest` belongs to.
+fn generate_lockfile(manifest: &str) {
+    let output = process::Command::new("cargo")
+        .args(["generate-lockfile", "--manifest-path", manifest])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "cargo generate-lockfile failed");
+}
+
+/// The version of a crate recorded in the lock file next to `root_manifest`.
+fn locked_version(root_manifest: &str, name: &str) -> String {
+    let lockfile = std::path::Path::new(root_manifest).with_file_name("Cargo.lock");
+    let lockfile: toml_edit::Document = fs::read_to_string(lockfile).unwrap().parse().unwrap();
+    let version = lockfile["package"]
+        .as_array_of_tables()
+        .unwrap()
+        .iter()
+        .find(|package| package["name"].as_str() == Some(name))
+        .and_then(|package| package["version"].as_str())
+        .unwrap()
+        .to_string();
+    version
+}
+
+#[test]
+fn upgrade_to_lockfile() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+
+    generate_lockfile(&root_manifest);
+
+    execute_command(&["upgrade", "--all", "--to-lockfile"], &root_manifest);
+
+    let libc = locked_version(&root_manifest, "libc");
+    let one = get_toml(&workspace_manifests[0]);
+    assert_eq!(one["dependencies"]["libc"].as_str(), Some(libc.as_str()));
+    assert_eq!(
+        one["dependencies"]["serde"]["version"].as_str(),
+        Some(locked_version(&root_manifest, "serde").as_str())
+    );
+    let two = get_toml(&workspace_manifests[1]);
+    assert_eq!(
+        two["dependencies"]["libc"]["version"].as_str(),
+        Some(libc.as_str())
+    );
+}
+
+#[test]
+fn upgrade_to_lockfile_keeps_operator() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    execute_command(&["add", "docopt", "--vers", "~0.8"], &manifest);
+    generate_lockfile(&manifest);
+
+    execute_command(&["upgrade", "--to-lockfile"], &manifest);
+
+    assert_eq!(
+        get_toml(&manifest)["dependencies"]["docopt"].as_str(),
+        Some(format!("~{}", locked_version(&manifest, "docopt")).as_str())
+    );
+}
+
+#[test]
+fn upgrade_update_lock() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    execute_command(&["add", "docopt", "--vers", "0.8"], &manifest);
+    generate_lockfile(&manifest);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-upgrade",
+        "upgrade",
+        "docopt@0.9",
+        "--update-lock",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .contains("docopt v0.8.")
+        .and()
+        .stdout()
+        .contains(" in Cargo.lock")
+        .unwrap();
+
+    assert!(locked_version(&manifest, "docopt").starts_with("0.9."));
+}
+
+#[test]
+fn upgrade_update_lock_keeps_other_versions() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    execute_command(&["add", "docopt", "--vers", "0.8"], &manifest);
+    execute_command(&["add", "itoa", "--vers", "0.4"], &manifest);
+    generate_lockfile(&manifest);
+    let itoa = locked_version(&manifest, "itoa");
+
+    execute_command(&["upgrade", "docopt@0.9", "--update-lock"], &manifest);
+
+    // Only the upgraded crate is updated in the lock file.
+    assert!(locked_version(&manifest, "docopt").starts_with("0.9."));
+    assert_eq!(locked_version(&manifest, "itoa"), itoa);
+}
+
+#[test]
+fn upgrade_unify_update_lock_leaves_lockfile() {
+    let (_tmpdir, root_manifest, _workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    generate_lockfile(&root_manifest);
+    let lockfile = std::path::Path::new(&root_manifest).with_file_name("Cargo.lock");
+    let original = fs::read_to_string(&lockfile).unwrap();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-upgrade",
+        "upgrade",
+        "--unify",
+        "--update-lock",
+        &format!("--manifest-path={}", root_manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .doesnt_contain("in Cargo.lock")
+        .unwrap();
+
+    assert_eq!(fs::read_to_string(&lockfile).unwrap(), original);
+}
+
 #[test]
 fn upgrade_specified_only() {
     let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
@@ -208,6 +379,154 @@ fn upgrade_at() {
     );
 }
 
+/// Run `cargo upgrade --check` with the given arguments, and get its exit code and output.
+fn check_upgrades(args: &[&str], manifest: &str) -> (Option<i32>, String) {
+    let output = process::Command::new("target/debug/cargo-upgrade")
+        .args(["upgrade", "--check"])
+        .args(args)
+        .arg(format!("--manifest-path={}", manifest))
+        .env("CARGO_IS_TEST", "1")
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    )
+}
+
+#[test]
+fn upgrade_check() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    execute_command(&["add", "docopt", "--vers", "0.8"], &manifest);
+    let before = snapshot(&[&manifest]);
+
+    let (code, stdout) = check_upgrades(&["docopt@0.8.3"], &manifest);
+    assert_eq!(code, Some(2));
+    assert!(stdout.contains("docopt 0.8 -> 0.8.3 in dependencies\n"));
+
+    let (code, stdout) = check_upgrades(&["docopt@0.9.0"], &manifest);
+    assert_eq!(code, Some(2));
+    assert!(stdout.contains("docopt 0.8 -> 0.9.0 in dependencies (incompatible)"));
+
+    let (code, stdout) = check_upgrades(&["docopt@0.8.0"], &manifest);
+    assert_eq!(code, Some(0));
+    assert!(stdout.contains("All dependencies are up to date."));
+
+    assert_eq!(before, snapshot(&[&manifest]));
+}
+
+#[test]
+fn upgrade_check_filters() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    execute_command(&["add", "docopt", "--vers", "0.8"], &manifest);
+
+    let (code, _) = check_upgrades(&["--incompatible", "docopt@0.9.0"], &manifest);
+    assert_eq!(code, Some(2));
+    let (code, _) = check_upgrades(&["--incompatible", "docopt@0.8.3"], &manifest);
+    assert_eq!(code, Some(0));
+
+    // The test versions of every crate are 1.0.0, 0.9.1, 0.8.3, 0.8.0 and 0.1.3.
+    let (code, stdout) = check_upgrades(&["--compatible", "docopt"], &manifest);
+    assert_eq!(code, Some(2));
+    assert!(stdout.contains("docopt 0.8 -> 0.8.3 in dependencies\n"));
+    let (code, _) = check_upgrades(&["--compatible", "docopt@0.9.0"], &manifest);
+    assert_eq!(code, Some(1));
+}
+
+#[test]
+fn upgrade_compatible() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    execute_command(&["add", "docopt", "--vers", "^0.8"], &manifest);
+    execute_command(&["add", "env_proxy", "--vers", "~0.1.1"], &manifest);
+    execute_command(&["add", "regex", "--vers", "1.0"], &manifest);
+
+    execute_command(&["upgrade", "--compatible"], &manifest);
+
+    // The test versions of every crate are 1.0.0, 0.9.1, 0.8.3, 0.8.0 and 0.1.3.
+    let dependencies = &get_toml(&manifest)["dependencies"];
+    assert_eq!(dependencies["docopt"].as_str(), Some("^0.8.3"));
+    assert_eq!(dependencies["env_proxy"].as_str(), Some("~0.1.3"));
+    assert_eq!(dependencies["regex"].as_str(), Some("1.0"));
+}
+
+#[test]
+fn upgrade_incompatible() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    execute_command(&["add", "docopt", "--vers", "0.8"], &manifest);
+    execute_command(&["add", "env_proxy", "--vers", "0.1.1"], &manifest);
+
+    execute_command(
+        &["upgrade", "--incompatible", "docopt@0.9.0", "env_proxy@0.1.3"],
+        &manifest,
+    );
+
+    let dependencies = &get_toml(&manifest)["dependencies"];
+    assert_eq!(dependencies["docopt"].as_str(), Some("0.9.0"));
+    assert_eq!(dependencies["env_proxy"].as_str(), Some("0.1.1"));
+}
+
+#[test]
+fn upgrade_json_messages() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    execute_command(&["add", "docopt", "--vers", "0.8"], &manifest);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-upgrade",
+        "upgrade",
+        "docopt@0.9.0",
+        "--message-format=json",
+        &format!("--manifest-path={}", manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .is(format!(
+            r#"{{"manifest_path":"{}","section":["dependencies"],"crate":"docopt","event":"upgraded","old":"0.8","new":"0.9.0"}}"#,
+            manifest
+        ).as_str())
+        .unwrap();
+
+    let (code, stdout) = check_upgrades(&["docopt@1.0.0", "--message-format=json"], &manifest);
+    assert_eq!(code, Some(2));
+    assert_eq!(
+        stdout,
+        format!(
+            r#"{{"manifest_path":"{}","section":["dependencies"],"crate":"docopt","event":"outdated","old":"0.9.0","new":"1.0.0","change":"breaking"}}
+"#,
+            manifest
+        )
+    );
+}
+
+#[test]
+fn upgrade_interactive() {
+    let (_tmpdir, manifest) = clone_out_test("tests/fixtures/add/Cargo.toml.sample");
+    execute_command(&["add", "docopt", "--vers", "0.8"], &manifest);
+    execute_command(&["add", "env_proxy", "--vers", "0.1.1"], &manifest);
+
+    let mut child = process::Command::new("target/debug/cargo-upgrade")
+        .args(["upgrade", "--interactive", "docopt@0.9.0", "env_proxy@0.1.3"])
+        .arg(format!("--manifest-path={}", manifest))
+        .env("CARGO_IS_TEST", "1")
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"y\nn\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Upgrade docopt 0.8 -> 0.9.0 (incompatible)? [y,n,a,q] \
+         Upgrade env_proxy 0.1.1 -> 0.1.3? [y,n,a,q] "
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("docopt v0.8 -> v0.9.0"));
+
+    let dependencies = &get_toml(&manifest)["dependencies"];
+    assert_eq!(dependencies["docopt"].as_str(), Some("0.9.0"));
+    assert_eq!(dependencies["env_proxy"].as_str(), Some("0.1.1"));
+}
+
 #[test]
 fn upgrade_workspace() {
     let (_tmpdir, root_manifest, workspace_manifests) = copy_workspace_test();
@@ -223,6 +542,176 @@ fn upgrade_workspace() {
     }
 }
 
+#[test]
+fn upgrade_workspace_dependencies() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-deps", &["one", "two"]);
+    let members = workspace_manifests
+        .iter()
+        .map(|manifest| get_toml(manifest).to_string())
+        .collect::<Vec<_>>();
+
+    execute_command(&["upgrade", "--all"], &root_manifest);
+
+    // The shared definition is upgraded, the members keep inheriting it.
+    assert_eq!(
+        get_toml(&root_manifest)["workspace"]["dependencies"]["libc"].as_str(),
+        Some("libc--CURRENT_VERSION_TEST")
+    );
+    for (manifest, original) in workspace_manifests.iter().zip(members) {
+        assert_eq!(get_toml(manifest).to_string(), original);
+    }
+}
+
+#[test]
+fn upgrade_unchanged_workspace_root_not_listed() {
+    let (_tmpdir, root_manifest, _workspace_manifests) =
+        copy_workspace_fixture("workspace-deps", &["one", "two"]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-upgrade",
+        "upgrade",
+        "--all",
+        "libc@0.2.28",
+        &format!("--manifest-path={}", root_manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .contains("one:")
+        .and()
+        .stdout()
+        .doesnt_contain("workspace:")
+        .unwrap();
+}
+
+#[test]
+fn upgrade_workspace_dependencies_from_member() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-deps", &["one", "two"]);
+
+    execute_command(&["upgrade"], &workspace_manifests[1]);
+
+    assert_eq!(
+        get_toml(&root_manifest)["workspace"]["dependencies"]["libc"].as_str(),
+        Some("libc--CURRENT_VERSION_TEST")
+    );
+}
+
+#[test]
+fn upgrade_unify() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    let two = get_toml(&workspace_manifests[1]).to_string();
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-upgrade",
+        "upgrade",
+        "--unify",
+        &format!("--manifest-path={}", root_manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .contains("one:")
+        .and()
+        .stdout()
+        .doesnt_contain("two:")
+        .unwrap();
+
+    let one = get_toml(&workspace_manifests[0]);
+    assert_eq!(one["dependencies"]["libc"].as_str(), Some("0.2.40"));
+    assert_eq!(
+        one["dependencies"]["serde"]["version"].as_str(),
+        Some("1.0.80")
+    );
+    assert!(!one["dependencies"]["serde"]["features"].is_none());
+    // `two` already requires the highest versions.
+    assert_eq!(get_toml(&workspace_manifests[1]).to_string(), two);
+}
+
+#[test]
+fn upgrade_unify_keeps_operators() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    execute_command(&["add", "serde", "--vers", "~1.0"], &workspace_manifests[2]);
+
+    execute_command(&["upgrade", "--unify"], &root_manifest);
+
+    // Each requirement only has its minimum raised.
+    let one = get_toml(&workspace_manifests[0]);
+    assert_eq!(
+        one["dependencies"]["serde"]["version"].as_str(),
+        Some("1.0.80")
+    );
+    let three = get_toml(&workspace_manifests[2]);
+    assert_eq!(three["dependencies"]["serde"].as_str(), Some("~1.0.80"));
+}
+
+#[test]
+fn upgrade_unify_skips_incompatible_requirements() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    execute_command(&["add", "libc", "--vers", "1.0"], &workspace_manifests[2]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-upgrade",
+        "upgrade",
+        "--unify",
+        &format!("--manifest-path={}", root_manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .contains("Skipping libc: incompatible requirements 0.2.28, 0.2.40, 1.0")
+        .unwrap();
+
+    // `1.0` would be a breaking upgrade for `one` and `two`, the other crates are still unified.
+    let one = get_toml(&workspace_manifests[0]);
+    assert_eq!(one["dependencies"]["libc"].as_str(), Some("0.2.28"));
+    assert_eq!(
+        one["dependencies"]["serde"]["version"].as_str(),
+        Some("1.0.80")
+    );
+    let two = get_toml(&workspace_manifests[1]);
+    assert_eq!(two["dependencies"]["libc"]["version"].as_str(), Some("0.2.40"));
+}
+
+#[test]
+fn upgrade_unify_skips_exact_pins() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+    execute_command(&["add", "serde", "--vers", "=1.0.90"], &workspace_manifests[2]);
+
+    assert_cli::Assert::command(&[
+        "target/debug/cargo-upgrade",
+        "upgrade",
+        "--unify",
+        &format!("--manifest-path={}", root_manifest),
+    ]).succeeds()
+        .and()
+        .stdout()
+        .contains("Skipping serde: incompatible requirements 1.0, 1.0.80, =1.0.90")
+        .unwrap();
+
+    // The pin of `three` does not spread to the others.
+    let one = get_toml(&workspace_manifests[0]);
+    assert_eq!(one["dependencies"]["serde"]["version"].as_str(), Some("1.0"));
+    assert_eq!(one["dependencies"]["libc"].as_str(), Some("0.2.40"));
+}
+
+#[test]
+fn upgrade_unify_only_given_dependencies() {
+    let (_tmpdir, root_manifest, workspace_manifests) =
+        copy_workspace_fixture("workspace-hoist", &["one", "two", "three"]);
+
+    execute_command(&["upgrade", "--unify", "serde"], &root_manifest);
+
+    let one = get_toml(&workspace_manifests[0]);
+    assert_eq!(one["dependencies"]["libc"].as_str(), Some("0.2.28"));
+    assert_eq!(
+        one["dependencies"]["serde"]["version"].as_str(),
+        Some("1.0.80")
+    );
+}
+
 /// Detect if attempting to run against a workspace root and give a helpful warning.
 #[test]
 fn detect_workspace() {
@@ -236,10 +725,16 @@ fn detect_workspace() {
     ]).fails_with(1)
         .and()
         .stderr()
-        .is(
+        .is(format!(
             "Command failed due to unhandled error: Found virtual manifest, but this command \
-             requires running against an actual package in this workspace. Try adding `--all`.",
-        )
+             requires running against an actual package in this workspace.
+ --> {}:1:1
+  |
+1 | [workspace]
+  | ^
+  = help: use `--all` to upgrade all packages in the workspace",
+            root_manifest
+        ).as_str())
         .unwrap();
 }
 
@@ -255,17 +750,16 @@ fn invalid_manifest() {
     ]).fails_with(1)
         .and()
         .stderr()
-        .is(
-            "Command failed due to unhandled error: Unable to parse Cargo.toml
-
-Caused by: Manifest not valid TOML
-Caused by: TOML parse error at line 1, column 6
+        .is(format!(
+            "Command failed due to unhandled error: Manifest is not valid TOML
+ --> {}:1:6
   |
 1 | This is clearly not a valid Cargo.toml.
   |      ^
-Unexpected `i`
-Expected `=`",
-        )
+  = note: Unexpected `i`
+  = note: Expected `=`",
+            manifest
+        ).as_str())
         .unwrap();
 }
 
@@ -0,0 +1,3 @@
+extern crate cc;
+
+fn main() {}
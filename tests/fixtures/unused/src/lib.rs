@@ -0,0 +1,8 @@
+extern crate docopt;
+
+use json::Value;
+
+// rand::random() used to be called here.
+pub fn parse(input: &str) -> Value {
+    json::from_str(input).unwrap()
+}
@@ -0,0 +1,2 @@
+extern crate assert_cli;
+extern crate unused;
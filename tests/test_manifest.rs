@@ -1,4 +1,32 @@
 extern crate assert_cli;
+extern crate tempdir;
+extern crate toml_edit;
+
+use std::fs;
+
+mod utils;
+use utils::{clone_out_test, execute_command};
+
+/// The sample manifest, after adding `docopt`, with `\n` line endings.
+const EDITED: &str = r#"[package]
+name = "cargo-list-test-fixture"
+version = "0.0.0"
+
+[lib]
+path = "dummy.rs"
+
+[dependencies]
+docopt = "0.6.0"
+"#;
+
+/// Add `docopt` to a copy of a fixture in `tests/fixtures/line-endings`, and return the new
+/// contents of the manifest.
+fn add_to_fixture(fixture: &str) -> String {
+    let (_tmpdir, manifest) =
+        clone_out_test(&format!("tests/fixtures/line-endings/Cargo.toml.{}", fixture));
+    execute_command(&["add", "docopt", "--vers", "0.6.0"], &manifest);
+    fs::read_to_string(&manifest).unwrap()
+}
 
 #[test]
 fn invalid_manifest() {
@@ -11,21 +39,36 @@ fn invalid_manifest() {
         .and()
         .stderr()
         .is(
-            r#"Command failed due to unhandled error: Unable to parse Cargo.toml
-
-Caused by: Manifest not valid TOML
-Caused by: TOML parse error at line 6, column 7
+            r#"Command failed due to unhandled error: Manifest is not valid TOML
+ --> tests/fixtures/manifest-invalid/Cargo.toml.sample:6:7
   |
 6 | key = invalid-value
   |       ^
-Unexpected `i`
-Expected `digit`, `-` or `+`
-expected 4 more elements
-expected 2 more elements
-While parsing a Time
-While parsing a Date-Time
-While parsing a Float
-While parsing an Integer"#,
+  = note: Unexpected `i`
+  = note: Expected `digit`, `-` or `+`"#,
         )
         .unwrap();
 }
+
+#[test]
+fn preserves_crlf() {
+    assert_eq!(add_to_fixture("crlf"), EDITED.replace('\n', "\r\n"));
+}
+
+#[test]
+fn preserves_byte_order_mark() {
+    assert_eq!(add_to_fixture("bom"), format!("\u{feff}{}", EDITED));
+}
+
+#[test]
+fn preserves_missing_final_newline() {
+    assert_eq!(add_to_fixture("no-final-newline"), EDITED.trim_end());
+}
+
+#[test]
+fn preserves_all_of_them() {
+    assert_eq!(
+        add_to_fixture("all"),
+        format!("\u{feff}{}", EDITED.trim_end().replace('\n', "\r\n"))
+    );
+}
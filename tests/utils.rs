@@ -3,9 +3,11 @@ extern crate toml_edit;
 
 use std::ffi::OsStr;
 use std::io::prelude::*;
+use std::path::Path;
 use std::{fs, process};
 
 /// Create temporary working directory with Cargo.toml manifest
+#[allow(dead_code)]
 pub fn clone_out_test(source: &str) -> (tempdir::TempDir, String) {
     let tmpdir =
         tempdir::TempDir::new("cargo-edit-test").expect("failed to construct temporary directory");
@@ -22,7 +24,96 @@ pub fn clone_out_test(source: &str) -> (tempdir::TempDir, String) {
     (tmpdir, path)
 }
 
+/// Helper function that copies the workspace test into a temporary directory.
+#[allow(dead_code)]
+pub fn copy_workspace_test() -> (tempdir::TempDir, String, Vec<String>) {
+    copy_workspace_fixture(
+        "workspace",
+        &["one", "two", "implicit/three", "explicit/four"],
+    )
+}
+
+/// Helper function that copies a workspace fixture (a root manifest, a `dummy.rs` and the manifests
+/// of the given members) into a temporary directory.
+#[allow(dead_code)]
+pub fn copy_workspace_fixture(
+    fixture: &str,
+    members: &[&str],
+) -> (tempdir::TempDir, String, Vec<String>) {
+    // Create a temporary directory and copy in the root manifest, the dummy rust file, and
+    // workspace member manifests.
+    let tmpdir = tempdir::TempDir::new("cargo-edit-workspace")
+        .expect("failed to construct temporary directory");
+
+    let (root_manifest_path, workspace_manifest_paths) = {
+        // Helper to copy in files to the temporary workspace. The standard library doesn't have a
+        // good equivalent of `cp -r`, hence this oddity.
+        let copy_in = |dir, file| {
+            let file_path = tmpdir
+                .path()
+                .join(dir)
+                .join(file)
+                .to_str()
+                .unwrap()
+                .to_string();
+
+            fs::create_dir_all(tmpdir.path().join(dir)).unwrap();
+
+            fs::copy(
+                format!("tests/fixtures/{}/{}/{}", fixture, dir, file),
+                &file_path,
+            )
+            .unwrap_or_else(|err| panic!("could not copy test file: {}", err));
+
+            file_path
+        };
+
+        let root_manifest_path = copy_in(".", "Cargo.toml");
+        copy_in(".", "dummy.rs");
+
+        let workspace_manifest_paths = members
+            .iter()
+            .map(|member| copy_in(member, "Cargo.toml"))
+            .collect::<Vec<_>>();
+
+        (root_manifest_path, workspace_manifest_paths)
+    };
+
+    (
+        tmpdir,
+        root_manifest_path,
+        workspace_manifest_paths.to_owned(),
+    )
+}
+
+/// Copy a fixture package (its manifest and source files) into a temporary directory, and return
+/// the path to its manifest.
+#[allow(dead_code)]
+pub fn copy_package_fixture(fixture: &str) -> (tempdir::TempDir, String) {
+    fn copy_dir(from: &Path, to: &Path) {
+        fs::create_dir_all(to).unwrap();
+        for entry in fs::read_dir(from).unwrap() {
+            let path = entry.unwrap().path();
+            let target = to.join(path.file_name().unwrap());
+            if path.is_dir() {
+                copy_dir(&path, &target);
+            } else {
+                fs::copy(&path, &target)
+                    .unwrap_or_else(|err| panic!("could not copy test file: {}", err));
+            }
+        }
+    }
+
+    let tmpdir = tempdir::TempDir::new("cargo-edit-package")
+        .expect("failed to construct temporary directory");
+    copy_dir(&Path::new("tests/fixtures").join(fixture), tmpdir.path());
+    let manifest = tmpdir.path().join("Cargo.toml").to_str().unwrap().to_string();
+
+    (tmpdir, manifest)
+}
+
 /// Execute localc cargo command, includes `--manifest-path`
+#[allow(dead_code)]
 pub fn execute_command<S>(command: &[S], manifest: &str)
 where
     S: AsRef<OsStr>,
@@ -45,6 +136,7 @@ where
 }
 
 /// Parse a manifest file as TOML
+#[allow(dead_code)]
 pub fn get_toml(manifest_path: &str) -> toml_edit::Document {
     let mut f = fs::File::open(manifest_path).unwrap();
     let mut s = String::new();